@@ -0,0 +1,168 @@
+#[cfg(unix)] use std::env;
+use std::io::{self, Read, Write};
+use std::string::ToString;
+use std::vec::Vec;
+#[cfg(unix)] use std::os::unix::net::UnixStream;
+#[cfg(unix)] use std::path::Path;
+
+use byteorder::{BigEndian, WriteBytesExt};
+use zeroize::Zeroizing;
+
+use openssh::parse_public_blob;
+use wire::{Reader, write_string};
+use super::{Identity, Constraint};
+use super::{read_message, write_message, write_private_key, private_key_len};
+use super::{write_constraint};
+use super::{SSH_AGENT_FAILURE, SSH_AGENT_SUCCESS};
+use super::{SSH_AGENTC_REQUEST_IDENTITIES, SSH_AGENT_IDENTITIES_ANSWER};
+use super::{SSH_AGENTC_SIGN_REQUEST, SSH_AGENT_SIGN_RESPONSE};
+use super::{SSH_AGENTC_ADD_IDENTITY, SSH_AGENTC_ADD_ID_CONSTRAINED};
+use super::{SSH_AGENTC_REMOVE_IDENTITY, SSH_AGENTC_REMOVE_ALL_IDENTITIES};
+use super::{SSH_AGENTC_LOCK, SSH_AGENTC_UNLOCK};
+use {PublicKey, PrivateKey, Signature, Error};
+
+
+/// Client of the ssh-agent
+///
+/// Works over any bidirectional stream, use
+/// [`connect_env`](#method.connect_env) to connect to the agent specified in
+/// `SSH_AUTH_SOCK` environment variable.
+#[derive(Debug)]
+pub struct Client<S> {
+    stream: S,
+}
+
+#[cfg(unix)]
+impl Client<UnixStream> {
+    /// Connect to the agent listening on the unix socket
+    pub fn connect<P: AsRef<Path>>(path: P) -> Result<Client<UnixStream>, Error> {
+        Ok(Client::new(UnixStream::connect(path)?))
+    }
+    /// Connect to the agent specified by `SSH_AUTH_SOCK` environment variable
+    pub fn connect_env() -> Result<Client<UnixStream>, Error> {
+        let path = env::var_os("SSH_AUTH_SOCK")
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound,
+                                          "SSH_AUTH_SOCK is not set"))?;
+        Client::connect(path)
+    }
+}
+
+impl<S: Read + Write> Client<S> {
+    /// Create a client using the already connected stream
+    pub fn new(stream: S) -> Client<S> {
+        Client { stream: stream }
+    }
+    /// Return underlying stream
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+    fn request(&mut self, msg: &[u8]) -> Result<Vec<u8>, Error> {
+        write_message(&mut self.stream, msg)?;
+        let reply = read_message(&mut self.stream)?
+            .ok_or(Error::InvalidFormat)?;
+        if reply[0] == SSH_AGENT_FAILURE {
+            return Err(Error::AgentFailure);
+        }
+        return Ok(reply);
+    }
+    fn request_success(&mut self, msg: &[u8]) -> Result<(), Error> {
+        let reply = self.request(msg)?;
+        if reply[0] != SSH_AGENT_SUCCESS {
+            return Err(Error::InvalidFormat);
+        }
+        Ok(())
+    }
+    /// List keys stored in the agent
+    ///
+    /// Keys of types unsupported by this library are skipped.
+    pub fn list_identities(&mut self) -> Result<Vec<Identity>, Error> {
+        let reply = self.request(&[SSH_AGENTC_REQUEST_IDENTITIES])?;
+        if reply[0] != SSH_AGENT_IDENTITIES_ANSWER {
+            return Err(Error::InvalidFormat);
+        }
+        let mut cur = Reader::new(&reply[1..]);
+        let num = cur.read_u32()?;
+        let mut result = Vec::new();
+        for _ in 0..num {
+            let blob = cur.read_bytes()?;
+            let comment = cur.read_string()?;
+            match parse_public_blob(blob) {
+                Ok(key) => result.push(Identity {
+                    key: key,
+                    comment: comment.to_string(),
+                }),
+                Err(Error::UnsupportedType(..)) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(result)
+    }
+    /// Sign data with the key stored in agent
+    ///
+    /// Flags may contain [`RSA_SHA2_256`](constant.RSA_SHA2_256.html) or
+    /// [`RSA_SHA2_512`](constant.RSA_SHA2_512.html) to request SHA-2
+    /// signatures for RSA keys, otherwise legacy `ssh-rsa` signature is made.
+    pub fn sign(&mut self, key: &PublicKey, data: &[u8], flags: u32)
+        -> Result<Signature, Error>
+    {
+        let mut msg = vec![SSH_AGENTC_SIGN_REQUEST];
+        let mut blob = Vec::with_capacity(512);
+        key.write_blob(&mut blob);
+        write_string(&mut msg, &blob);
+        write_string(&mut msg, data);
+        msg.write_u32::<BigEndian>(flags).unwrap();
+        let reply = self.request(&msg)?;
+        if reply[0] != SSH_AGENT_SIGN_RESPONSE {
+            return Err(Error::InvalidFormat);
+        }
+        let mut cur = Reader::new(&reply[1..]);
+        Signature::from_bytes(cur.read_bytes()?)
+    }
+    /// Add private key to the agent
+    pub fn add_identity(&mut self, key: &PrivateKey, comment: &str,
+                        constraints: &[Constraint])
+        -> Result<(), Error>
+    {
+        // constraints aren't secret, so they are serialized separately to
+        // know the exact size of the message
+        let mut tail = Vec::with_capacity(64);
+        for constraint in constraints {
+            write_constraint(&mut tail, constraint);
+        }
+        let len = 1 + private_key_len(key) + 4 + comment.len() + tail.len();
+        let mut msg = Zeroizing::new(Vec::with_capacity(len));
+        if constraints.is_empty() {
+            msg.push(SSH_AGENTC_ADD_IDENTITY);
+        } else {
+            msg.push(SSH_AGENTC_ADD_ID_CONSTRAINED);
+        }
+        write_private_key(&mut msg, key);
+        write_string(&mut msg, comment.as_bytes());
+        msg.extend_from_slice(&tail);
+        self.request_success(&msg)
+    }
+    /// Remove key from the agent
+    pub fn remove_identity(&mut self, key: &PublicKey) -> Result<(), Error> {
+        let mut msg = vec![SSH_AGENTC_REMOVE_IDENTITY];
+        let mut blob = Vec::with_capacity(512);
+        key.write_blob(&mut blob);
+        write_string(&mut msg, &blob);
+        self.request_success(&msg)
+    }
+    /// Remove all keys from the agent
+    pub fn remove_all_identities(&mut self) -> Result<(), Error> {
+        self.request_success(&[SSH_AGENTC_REMOVE_ALL_IDENTITIES])
+    }
+    /// Lock the agent with a passphrase
+    pub fn lock(&mut self, passphrase: &str) -> Result<(), Error> {
+        let mut msg = Zeroizing::new(vec![SSH_AGENTC_LOCK]);
+        write_string(&mut msg, passphrase.as_bytes());
+        self.request_success(&msg)
+    }
+    /// Unlock the agent previously locked with a passphrase
+    pub fn unlock(&mut self, passphrase: &str) -> Result<(), Error> {
+        let mut msg = Zeroizing::new(vec![SSH_AGENTC_UNLOCK]);
+        write_string(&mut msg, passphrase.as_bytes());
+        self.request_success(&msg)
+    }
+}
@@ -0,0 +1,239 @@
+//! SSH agent protocol
+//!
+//! The protocol is described in
+//! [draft-miller-ssh-agent](https://tools.ietf.org/html/draft-miller-ssh-agent).
+//! See [`Client`](struct.Client.html) for talking to the running agent
+//! and [`Server`](struct.Server.html) for implementing one.
+use std::io::{self, Read, Write};
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+
+use openssh::{parse_public_blob, write_private_key, private_key_len};
+use wire::{Reader, write_string};
+use {PublicKey, Error};
+
+mod client;
+mod server;
+
+pub use self::client::Client;
+pub use self::server::{Server, Session, KeyStore, MemoryStore};
+
+
+/// Flag for `sign` requesting `rsa-sha2-256` signature for RSA keys
+pub const RSA_SHA2_256: u32 = 2;
+/// Flag for `sign` requesting `rsa-sha2-512` signature for RSA keys
+pub const RSA_SHA2_512: u32 = 4;
+
+/// Maximum message length, same limit as in OpenSSH
+const MAX_MESSAGE_LEN: usize = 256*1024;
+
+const SSH_AGENT_FAILURE: u8 = 5;
+const SSH_AGENT_SUCCESS: u8 = 6;
+const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+const SSH_AGENTC_SIGN_REQUEST: u8 = 13;
+const SSH_AGENT_SIGN_RESPONSE: u8 = 14;
+const SSH_AGENTC_ADD_IDENTITY: u8 = 17;
+const SSH_AGENTC_REMOVE_IDENTITY: u8 = 18;
+const SSH_AGENTC_REMOVE_ALL_IDENTITIES: u8 = 19;
+const SSH_AGENTC_LOCK: u8 = 22;
+const SSH_AGENTC_UNLOCK: u8 = 23;
+const SSH_AGENTC_ADD_ID_CONSTRAINED: u8 = 25;
+const SSH_AGENTC_EXTENSION: u8 = 27;
+
+const SSH_AGENT_CONSTRAIN_LIFETIME: u8 = 1;
+const SSH_AGENT_CONSTRAIN_CONFIRM: u8 = 2;
+const SSH_AGENT_CONSTRAIN_EXTENSION: u8 = 255;
+
+const RESTRICT_DESTINATION: &str = "restrict-destination-v00@openssh.com";
+
+/// Key stored in the agent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    /// Public key
+    pub key: PublicKey,
+    /// Comment (usually the file name the key was loaded from)
+    pub comment: String,
+}
+
+/// Constraint on the key usage when adding key to the agent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Constraint {
+    /// Remove key after the specified number of seconds
+    Lifetime(u32),
+    /// Require explicit confirmation for each use of the key
+    Confirm,
+    /// Only allow using the key for the listed hops
+    /// (`restrict-destination-v00@openssh.com` extension, see `ssh-add -h`)
+    RestrictDestination(Vec<DestinationConstraint>),
+    /// Vendor extension: name and raw extension contents
+    Extension(String, Vec<u8>),
+}
+
+/// Single permitted hop of the destination-constrained key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DestinationConstraint {
+    /// Host the connection originates from (empty hop means local use)
+    pub from: Hop,
+    /// Host the key may be used to authenticate to
+    pub to: Hop,
+}
+
+/// Host description in the destination constraint
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Hop {
+    /// User name pattern (only valid for `to` hop)
+    pub user: Option<String>,
+    /// Host name (informational, hosts are identified by keys)
+    pub hostname: Option<String>,
+    /// Host keys of the host
+    pub host_keys: Vec<HostKey>,
+}
+
+/// Host key in the destination constraint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostKey {
+    /// The public key
+    pub key: PublicKey,
+    /// Key is a certificate authority for host certificates
+    pub is_ca: bool,
+}
+
+/// Writes message with a length prefix into the stream
+fn write_message<W: Write>(stream: &mut W, msg: &[u8]) -> Result<(), Error> {
+    let mut len = [0u8; 4];
+    BigEndian::write_u32(&mut len, msg.len() as u32);
+    stream.write_all(&len)?;
+    stream.write_all(msg)?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Reads a length-prefixed message from the stream
+///
+/// Returns `None` on end of stream before the message
+fn read_message<R: Read>(stream: &mut R) -> Result<Option<Vec<u8>>, Error> {
+    let mut len = [0u8; 4];
+    match stream.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    }
+    let len = BigEndian::read_u32(&len) as usize;
+    if len == 0 || len > MAX_MESSAGE_LEN {
+        return Err(Error::InvalidFormat);
+    }
+    let mut buf = vec![0u8; len];
+    stream.read_exact(&mut buf)?;
+    Ok(Some(buf))
+}
+
+fn write_constraint(buf: &mut Vec<u8>, constraint: &Constraint) {
+    match *constraint {
+        Constraint::Lifetime(seconds) => {
+            buf.push(SSH_AGENT_CONSTRAIN_LIFETIME);
+            buf.write_u32::<BigEndian>(seconds).unwrap();
+        }
+        Constraint::Confirm => {
+            buf.push(SSH_AGENT_CONSTRAIN_CONFIRM);
+        }
+        Constraint::RestrictDestination(ref destinations) => {
+            buf.push(SSH_AGENT_CONSTRAIN_EXTENSION);
+            write_string(buf, RESTRICT_DESTINATION.as_bytes());
+            let mut constraints = Vec::new();
+            for dest in destinations {
+                let mut item = Vec::new();
+                write_hop(&mut item, &dest.from);
+                write_hop(&mut item, &dest.to);
+                write_string(&mut item, b"");  // reserved
+                write_string(&mut constraints, &item);
+            }
+            write_string(buf, &constraints);
+        }
+        Constraint::Extension(ref name, ref data) => {
+            buf.push(SSH_AGENT_CONSTRAIN_EXTENSION);
+            write_string(buf, name.as_bytes());
+            buf.extend(data);
+        }
+    }
+}
+
+fn write_hop(buf: &mut Vec<u8>, hop: &Hop) {
+    let mut data = Vec::new();
+    write_string(&mut data,
+        hop.user.as_ref().map(|x| x.as_bytes()).unwrap_or(b""));
+    write_string(&mut data,
+        hop.hostname.as_ref().map(|x| x.as_bytes()).unwrap_or(b""));
+    write_string(&mut data, b"");  // reserved
+    for host_key in &hop.host_keys {
+        let mut blob = Vec::with_capacity(512);
+        host_key.key.write_blob(&mut blob);
+        write_string(&mut data, &blob);
+        data.push(host_key.is_ca as u8);
+    }
+    write_string(buf, &data);
+}
+
+/// Reads a constraint of `SSH_AGENTC_ADD_ID_CONSTRAINED` message
+///
+/// Unknown extensions are rejected as there is no way to skip them.
+fn read_constraint(cur: &mut Reader) -> Result<Constraint, Error> {
+    match cur.read_byte()? {
+        SSH_AGENT_CONSTRAIN_LIFETIME => Ok(Constraint::Lifetime(cur.read_u32()?)),
+        SSH_AGENT_CONSTRAIN_CONFIRM => Ok(Constraint::Confirm),
+        SSH_AGENT_CONSTRAIN_EXTENSION => {
+            let name = cur.read_string()?;
+            if name != RESTRICT_DESTINATION {
+                return Err(Error::UnsupportedType(name.to_string()));
+            }
+            let mut constraints = Reader::new(cur.read_bytes()?);
+            let mut result = Vec::new();
+            while !constraints.is_empty() {
+                let mut item = Reader::new(constraints.read_bytes()?);
+                let from = read_hop(&mut item)?;
+                let to = read_hop(&mut item)?;
+                let _reserved = item.read_bytes()?;
+                if !item.is_empty() {
+                    return Err(Error::InvalidFormat);
+                }
+                // same sanity checks as in OpenSSH
+                if from.user.is_some() ||
+                    from.hostname.is_some() == from.host_keys.is_empty() ||
+                    to.hostname.is_none() || to.host_keys.is_empty()
+                {
+                    return Err(Error::InvalidFormat);
+                }
+                result.push(DestinationConstraint { from: from, to: to });
+            }
+            Ok(Constraint::RestrictDestination(result))
+        }
+        _ => Err(Error::InvalidFormat),
+    }
+}
+
+fn read_hop(cur: &mut Reader) -> Result<Hop, Error> {
+    let non_empty = |x: &str| if x.is_empty() {
+        None
+    } else {
+        Some(x.to_string())
+    };
+    let mut cur = Reader::new(cur.read_bytes()?);
+    let user = non_empty(cur.read_string()?);
+    let hostname = non_empty(cur.read_string()?);
+    let _reserved = cur.read_bytes()?;
+    let mut host_keys = Vec::new();
+    while !cur.is_empty() {
+        let key = parse_public_blob(cur.read_bytes()?)?;
+        let is_ca = match cur.read_byte()? {
+            0 => false,
+            1 => true,
+            _ => return Err(Error::InvalidFormat),
+        };
+        host_keys.push(HostKey { key: key, is_ca: is_ca });
+    }
+    Ok(Hop { user: user, hostname: hostname, host_keys: host_keys })
+}
@@ -0,0 +1,620 @@
+use std::fmt;
+use std::io::{Read, Write};
+use std::boxed::Box;
+use std::string::{String, ToString};
+use std::sync::Mutex;
+use std::vec::Vec;
+use std::time::{SystemTime, UNIX_EPOCH};
+#[cfg(unix)] use std::os::unix::net::UnixListener;
+#[cfg(unix)] use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use byteorder::{BigEndian, WriteBytesExt};
+use hmac::{Hmac, Mac};
+use rand_core::{OsRng, RngCore};
+use sha2::Sha512;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+use allowed_signers::match_pattern;
+use openssh::{parse_public_blob, read_private_key};
+use wire::{Reader, write_string};
+use super::{Constraint, DestinationConstraint, Hop, Identity};
+use super::{read_message, write_message, read_constraint};
+use super::{SSH_AGENT_FAILURE, SSH_AGENT_SUCCESS};
+use super::{SSH_AGENTC_REQUEST_IDENTITIES, SSH_AGENT_IDENTITIES_ANSWER};
+use super::{SSH_AGENTC_SIGN_REQUEST, SSH_AGENT_SIGN_RESPONSE};
+use super::{SSH_AGENTC_ADD_IDENTITY, SSH_AGENTC_ADD_ID_CONSTRAINED};
+use super::{SSH_AGENTC_REMOVE_IDENTITY, SSH_AGENTC_REMOVE_ALL_IDENTITIES};
+use super::{SSH_AGENTC_LOCK, SSH_AGENTC_UNLOCK, SSH_AGENTC_EXTENSION};
+use super::{RSA_SHA2_256, RSA_SHA2_512};
+use {PublicKey, PrivateKey, SignatureAlgorithm, Signature, Error};
+
+/// Same limits as in OpenSSH
+const MAX_SESSION_IDS: usize = 16;
+const MAX_SESSION_ID_LEN: usize = 128;
+
+const SSH2_MSG_USERAUTH_REQUEST: u8 = 50;
+
+/// PBKDF2 rounds for the lock passphrase
+const LOCK_KDF_ROUNDS: u32 = 10000;
+/// Failed unlock attempts are delayed by 100ms times the number of failures,
+/// up to 10 seconds (as in OpenSSH)
+const UNLOCK_DELAY_MS: u64 = 100;
+const MAX_UNLOCK_FAILURES: u32 = 100;
+
+type ConfirmFn = dyn Fn(&Identity) -> bool + Send + Sync;
+
+
+/// Storage of the agent keys
+///
+/// Implement this trait to keep keys somewhere other than process memory.
+/// Public key is used as the identity of the stored key. Private keys never
+/// leave the store, it signs the data itself.
+///
+/// Key constraints (lifetime, confirmation, destinations) are kept by the
+/// [`Server`](struct.Server.html), keys which are already in the store when
+/// the server starts are unconstrained.
+pub trait KeyStore {
+    /// List public keys and comments of all stored keys
+    fn identities(&self) -> Result<Vec<(PublicKey, String)>, Error>;
+    /// Add a key, replacing previously stored key with the same public key
+    fn add(&mut self, key: PrivateKey, comment: String) -> Result<(), Error>;
+    /// Remove a key, returns `false` if there was no such key
+    fn remove(&mut self, key: &PublicKey) -> Result<bool, Error>;
+    /// Remove all keys
+    fn remove_all(&mut self) -> Result<(), Error>;
+    /// Find key by its public part, returns the comment of the key
+    fn find(&self, key: &PublicKey) -> Result<Option<String>, Error> {
+        Ok(self.identities()?.into_iter()
+            .find(|&(ref k, _)| k == key)
+            .map(|(_, comment)| comment))
+    }
+    /// Sign data with the stored key
+    ///
+    /// Returns `Error::AgentFailure` if there is no such key.
+    fn sign(&self, key: &PublicKey, data: &[u8], alg: SignatureAlgorithm)
+        -> Result<Signature, Error>;
+}
+
+/// Key store that keeps keys in memory
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    keys: Vec<(PrivateKey, String)>,
+}
+
+/// In-process ssh-agent
+///
+/// Requests are handled by [`handle`](#method.handle) or, for a whole
+/// connection, by [`serve`](#method.serve). Connections are independent but
+/// share keys and the lock state.
+pub struct Server<K> {
+    state: Mutex<State<K>>,
+    confirm: Option<Box<ConfirmFn>>,
+}
+
+struct State<K> {
+    store: K,
+    constraints: Vec<(PublicKey, KeyConstraints)>,
+    lock: Option<Lock>,
+    unlock_failures: u32,
+}
+
+/// Salted hash of the passphrase the agent is locked with
+struct Lock {
+    salt: [u8; 16],
+    hash: Vec<u8>,
+}
+
+/// Constraints of the stored key
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+struct KeyConstraints {
+    /// Unix timestamp when the key must be removed from the agent
+    expires: Option<u64>,
+    /// Each use of the key must be confirmed
+    confirm: bool,
+    /// Hops the key is allowed to be used for, empty means unrestricted
+    destinations: Vec<DestinationConstraint>,
+}
+
+/// Per-connection state of the agent
+///
+/// Holds session identifiers bound by `session-bind@openssh.com` which are
+/// used to check destination constraints.
+#[derive(Debug, Default)]
+pub struct Session {
+    binds: Vec<SessionBind>,
+    bind_attempted: bool,
+}
+
+#[derive(Debug)]
+struct SessionBind {
+    host_key: PublicKey,
+    session_id: Vec<u8>,
+    forwarded: bool,
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// PBKDF2-HMAC-SHA512, single output block
+fn hash_passphrase(salt: &[u8], passphrase: &[u8]) -> Vec<u8> {
+    let mac = Hmac::<Sha512>::new_from_slice(passphrase)
+        .expect("HMAC accepts keys of any size");
+    let mut block = mac.clone()
+        .chain_update(salt)
+        .chain_update(1u32.to_be_bytes())
+        .finalize().into_bytes();
+    let mut result = block;
+    for _ in 1..LOCK_KDF_ROUNDS {
+        block = mac.clone().chain_update(block).finalize().into_bytes();
+        for (r, b) in result.iter_mut().zip(block.iter()) {
+            *r ^= *b;
+        }
+    }
+    return result.to_vec();
+}
+
+impl MemoryStore {
+    /// Create an empty store
+    pub fn new() -> MemoryStore {
+        MemoryStore { keys: Vec::new() }
+    }
+}
+
+impl KeyStore for MemoryStore {
+    fn identities(&self) -> Result<Vec<(PublicKey, String)>, Error> {
+        Ok(self.keys.iter()
+            .map(|&(ref k, ref comment)| (k.public_key(), comment.clone()))
+            .collect())
+    }
+    fn add(&mut self, key: PrivateKey, comment: String) -> Result<(), Error> {
+        let old = self.keys.iter_mut()
+            .find(|&&mut (ref k, _)| k.public_key_ref() == key.public_key_ref());
+        match old {
+            Some(old) => *old = (key, comment),
+            None => self.keys.push((key, comment)),
+        }
+        Ok(())
+    }
+    fn remove(&mut self, key: &PublicKey) -> Result<bool, Error> {
+        let len = self.keys.len();
+        self.keys.retain(|&(ref k, _)| !k.matches(key));
+        Ok(self.keys.len() != len)
+    }
+    fn remove_all(&mut self) -> Result<(), Error> {
+        self.keys.clear();
+        Ok(())
+    }
+    fn find(&self, key: &PublicKey) -> Result<Option<String>, Error> {
+        Ok(self.keys.iter()
+            .find(|&&(ref k, _)| k.matches(key))
+            .map(|&(_, ref comment)| comment.clone()))
+    }
+    fn sign(&self, key: &PublicKey, data: &[u8], alg: SignatureAlgorithm)
+        -> Result<Signature, Error>
+    {
+        let &(ref private, _) = self.keys.iter()
+            .find(|&&(ref k, _)| k.matches(key))
+            .ok_or(Error::AgentFailure)?;
+        private.sign(data, alg)
+    }
+}
+
+impl<K> fmt::Debug for Server<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Server")
+            .field("confirm", &self.confirm.is_some())
+            .finish()
+    }
+}
+
+impl<K: KeyStore> Server<K> {
+    /// Create an agent serving keys from the store
+    pub fn new(store: K) -> Server<K> {
+        Server {
+            state: Mutex::new(State {
+                store: store,
+                constraints: Vec::new(),
+                lock: None,
+                unlock_failures: 0,
+            }),
+            confirm: None,
+        }
+    }
+    /// Set a function which confirms use of keys added with `Confirm`
+    /// constraint
+    ///
+    /// Without this function such keys can't be used at all. The function
+    /// is called without holding any locks, so it may wait for the user.
+    pub fn set_confirm<F>(&mut self, confirm: F)
+        where F: Fn(&Identity) -> bool + Send + Sync + 'static
+    {
+        self.confirm = Some(Box::new(confirm));
+    }
+    /// Serve a single connection until the end of stream
+    pub fn serve<S: Read + Write>(&self, mut stream: S) -> Result<(), Error> {
+        let mut session = Session::new();
+        while let Some(request) = read_message(&mut stream)? {
+            // requests may contain private keys and passphrases
+            let request = Zeroizing::new(request);
+            let reply = self.handle(&mut session, &request);
+            write_message(&mut stream, &reply)?;
+        }
+        Ok(())
+    }
+    /// Handle a single request (without length prefix) and return the reply
+    pub fn handle(&self, session: &mut Session, request: &[u8]) -> Vec<u8> {
+        if request.is_empty() {
+            return vec![SSH_AGENT_FAILURE];
+        }
+        match self.process(session, request) {
+            Ok(reply) => reply,
+            Err(_) => vec![SSH_AGENT_FAILURE],
+        }
+    }
+    fn process(&self, session: &mut Session, request: &[u8])
+        -> Result<Vec<u8>, Error>
+    {
+        let mut cur = Reader::new(&request[1..]);
+        let mut state = self.state.lock().map_err(|_| Error::AgentFailure)?;
+        state.remove_expired()?;
+        if state.lock.is_some() && request[0] != SSH_AGENTC_UNLOCK {
+            if request[0] == SSH_AGENTC_REQUEST_IDENTITIES {
+                return Ok(vec![SSH_AGENT_IDENTITIES_ANSWER, 0, 0, 0, 0]);
+            }
+            return Err(Error::AgentFailure);
+        }
+        match request[0] {
+            SSH_AGENTC_REQUEST_IDENTITIES => {
+                let mut reply = vec![SSH_AGENT_IDENTITIES_ANSWER];
+                let keys = state.store.identities()?.into_iter()
+                    .filter(|&(ref key, _)| {
+                        session.permitted(&state.constraints(key).destinations,
+                                          None)
+                    })
+                    .map(|(key, comment)| Identity {
+                        key: key,
+                        comment: comment,
+                    })
+                    .collect::<Vec<_>>();
+                reply.write_u32::<BigEndian>(keys.len() as u32).unwrap();
+                for id in keys {
+                    let mut blob = Vec::with_capacity(512);
+                    id.key.write_blob(&mut blob);
+                    write_string(&mut reply, &blob);
+                    write_string(&mut reply, id.comment.as_bytes());
+                }
+                Ok(reply)
+            }
+            SSH_AGENTC_SIGN_REQUEST => {
+                let key = parse_public_blob(cur.read_bytes()?)?;
+                let data = cur.read_bytes()?;
+                let flags = cur.read_u32()?;
+                let comment = state.store.find(&key)?
+                    .ok_or(Error::AgentFailure)?;
+                let constraints = state.constraints(&key).clone();
+                drop(state);
+                if !constraints.destinations.is_empty() {
+                    session.check_sign(&constraints.destinations, &key, data)?;
+                }
+                if constraints.confirm {
+                    let id = Identity { key: key.clone(), comment: comment };
+                    let confirmed = self.confirm.as_ref()
+                        .map(|f| f(&id))
+                        .unwrap_or(false);
+                    if !confirmed {
+                        return Err(Error::AgentFailure);
+                    }
+                }
+                let alg = match key {
+                    PublicKey::Ed25519(..) => SignatureAlgorithm::Ed25519,
+                    PublicKey::Rsa { .. } if flags & RSA_SHA2_512 != 0 => {
+                        SignatureAlgorithm::RsaSha512
+                    }
+                    PublicKey::Rsa { .. } if flags & RSA_SHA2_256 != 0 => {
+                        SignatureAlgorithm::RsaSha256
+                    }
+                    PublicKey::Rsa { .. } => SignatureAlgorithm::RsaSha1,
+                    PublicKey::Ecdsa { curve, .. } => {
+                        SignatureAlgorithm::for_curve(curve)
+                    }
+                };
+                let state = self.state.lock()
+                    .map_err(|_| Error::AgentFailure)?;
+                // the key may have been replaced while waiting for
+                // confirmation
+                if *state.constraints(&key) != constraints {
+                    return Err(Error::AgentFailure);
+                }
+                let signature = state.store.sign(&key, data, alg)?;
+                let mut reply = vec![SSH_AGENT_SIGN_RESPONSE];
+                write_string(&mut reply, &signature.to_bytes());
+                Ok(reply)
+            }
+            SSH_AGENTC_ADD_IDENTITY | SSH_AGENTC_ADD_ID_CONSTRAINED => {
+                let key = read_private_key(&mut cur)?;
+                let comment = cur.read_string()?.to_string();
+                let mut constraints = KeyConstraints::default();
+                while request[0] == SSH_AGENTC_ADD_ID_CONSTRAINED &&
+                    !cur.is_empty()
+                {
+                    match read_constraint(&mut cur)? {
+                        Constraint::Lifetime(secs) => {
+                            constraints.expires = Some(now() + secs as u64);
+                        }
+                        Constraint::Confirm => constraints.confirm = true,
+                        Constraint::RestrictDestination(dest) => {
+                            constraints.destinations.extend(dest);
+                        }
+                        Constraint::Extension(..) => {
+                            return Err(Error::InvalidFormat);
+                        }
+                    }
+                }
+                if !cur.is_empty() {
+                    return Err(Error::InvalidFormat);
+                }
+                let public = key.public_key();
+                state.store.add(key, comment)?;
+                state.set_constraints(public, constraints);
+                Ok(vec![SSH_AGENT_SUCCESS])
+            }
+            SSH_AGENTC_REMOVE_IDENTITY => {
+                let key = parse_public_blob(cur.read_bytes()?)?;
+                state.constraints.retain(|&(ref k, _)| *k != key);
+                if !state.store.remove(&key)? {
+                    return Err(Error::AgentFailure);
+                }
+                Ok(vec![SSH_AGENT_SUCCESS])
+            }
+            SSH_AGENTC_REMOVE_ALL_IDENTITIES => {
+                state.constraints.clear();
+                state.store.remove_all()?;
+                Ok(vec![SSH_AGENT_SUCCESS])
+            }
+            SSH_AGENTC_LOCK => {
+                let mut salt = [0; 16];
+                OsRng.fill_bytes(&mut salt);
+                let hash = hash_passphrase(&salt, cur.read_bytes()?);
+                state.lock = Some(Lock { salt: salt, hash: hash });
+                Ok(vec![SSH_AGENT_SUCCESS])
+            }
+            SSH_AGENTC_UNLOCK => {
+                let passphrase = cur.read_bytes()?;
+                let matches = match state.lock {
+                    Some(ref lock) => {
+                        let hash = hash_passphrase(&lock.salt, passphrase);
+                        lock.hash.ct_eq(&hash).into()
+                    }
+                    None => false,
+                };
+                if !matches {
+                    // the state stays locked while sleeping, so that
+                    // guesses can't be made in parallel connections
+                    if state.unlock_failures < MAX_UNLOCK_FAILURES {
+                        state.unlock_failures += 1;
+                    }
+                    let delay = UNLOCK_DELAY_MS *
+                        state.unlock_failures as u64;
+                    thread::sleep(Duration::from_millis(delay));
+                    return Err(Error::AgentFailure);
+                }
+                state.unlock_failures = 0;
+                state.lock = None;
+                Ok(vec![SSH_AGENT_SUCCESS])
+            }
+            SSH_AGENTC_EXTENSION => {
+                drop(state);
+                match cur.read_string()? {
+                    "session-bind@openssh.com" => {
+                        session.bind_attempted = true;
+                        session.bind(&mut cur)?;
+                        Ok(vec![SSH_AGENT_SUCCESS])
+                    }
+                    _ => Err(Error::AgentFailure),
+                }
+            }
+            _ => Err(Error::AgentFailure),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl<K: KeyStore + Send + 'static> Server<K> {
+    /// Accept connections on the unix socket, each in a separate thread
+    ///
+    /// Note: access to the agent is controlled only by permissions of the
+    /// socket, so it should be placed in a directory accessible only by the
+    /// user.
+    pub fn serve_unix(self: Arc<Self>, listener: UnixListener)
+        -> Result<(), Error>
+    {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let server = self.clone();
+            thread::spawn(move || server.serve(stream));
+        }
+        Ok(())
+    }
+}
+
+impl<K: KeyStore> State<K> {
+    fn remove_expired(&mut self) -> Result<(), Error> {
+        let now = now();
+        let expired = self.constraints.iter()
+            .filter(|&&(_, ref c)| c.expires.map(|t| t <= now).unwrap_or(false))
+            .map(|&(ref key, _)| key.clone())
+            .collect::<Vec<_>>();
+        for key in expired {
+            self.constraints.retain(|&(ref k, _)| *k != key);
+            self.store.remove(&key)?;
+        }
+        Ok(())
+    }
+    fn constraints(&self, key: &PublicKey) -> &KeyConstraints {
+        static NONE: KeyConstraints = KeyConstraints {
+            expires: None,
+            confirm: false,
+            destinations: Vec::new(),
+        };
+        self.constraints.iter()
+            .find(|&&(ref k, _)| k == key)
+            .map(|&(_, ref c)| c)
+            .unwrap_or(&NONE)
+    }
+    fn set_constraints(&mut self, key: PublicKey, constraints: KeyConstraints) {
+        self.constraints.retain(|&(ref k, _)| *k != key);
+        self.constraints.push((key, constraints));
+    }
+}
+
+impl Session {
+    /// Create state for the new connection
+    pub fn new() -> Session {
+        Session::default()
+    }
+    fn bind(&mut self, cur: &mut Reader) -> Result<(), Error> {
+        let host_key = parse_public_blob(cur.read_bytes()?)?;
+        let session_id = cur.read_bytes()?;
+        let signature = Signature::from_bytes(cur.read_bytes()?)?;
+        let forwarded = cur.read_byte()? != 0;
+        if !cur.is_empty() || session_id.len() > MAX_SESSION_ID_LEN {
+            return Err(Error::InvalidFormat);
+        }
+        host_key.verify(session_id, &signature)?;
+        for bind in &self.binds {
+            // connection used for authentication can't be bound again
+            if !bind.forwarded {
+                return Err(Error::AgentFailure);
+            }
+            if bind.session_id == session_id {
+                if bind.host_key == host_key {
+                    return Ok(());
+                }
+                return Err(Error::KeyMismatch);
+            }
+        }
+        if self.binds.len() >= MAX_SESSION_IDS {
+            return Err(Error::AgentFailure);
+        }
+        self.binds.push(SessionBind {
+            host_key: host_key,
+            session_id: session_id.to_vec(),
+            forwarded: forwarded,
+        });
+        Ok(())
+    }
+    /// Checks that the path of this connection is permitted by constraints
+    ///
+    /// `user` is set when checking a signature request.
+    fn permitted(&self, destinations: &[DestinationConstraint],
+                 user: Option<&str>)
+        -> bool
+    {
+        if destinations.is_empty() {
+            return true;
+        }
+        if self.binds.is_empty() {
+            // local use is always allowed, unless binding failed
+            return !self.bind_attempted;
+        }
+        let mut from = None;
+        for (idx, bind) in self.binds.iter().enumerate() {
+            let test_user = if idx == self.binds.len() - 1 {
+                user
+            } else {
+                None
+            };
+            if !permitted_hop(destinations, from, Some(&bind.host_key),
+                              test_user)
+            {
+                return false;
+            }
+            from = Some(&bind.host_key);
+        }
+        // when listing keys through forwarded connection, only show keys
+        // that can be used beyond the last host
+        let last = &self.binds[self.binds.len() - 1];
+        if last.forwarded && user.is_none() &&
+            !permitted_hop(destinations, Some(&last.host_key), None, None)
+        {
+            return false;
+        }
+        return true;
+    }
+    fn check_sign(&self, destinations: &[DestinationConstraint],
+                  key: &PublicKey, data: &[u8])
+        -> Result<(), Error>
+    {
+        let last = self.binds.last().ok_or(Error::AgentFailure)?;
+        let (user, session_id, host_key) = parse_userauth_request(data, key)?;
+        if !self.permitted(destinations, Some(user)) ||
+            last.session_id != session_id ||
+            last.host_key != host_key ||
+            last.forwarded
+        {
+            return Err(Error::AgentFailure);
+        }
+        Ok(())
+    }
+}
+
+fn permitted_hop(destinations: &[DestinationConstraint],
+                 from: Option<&PublicKey>, to: Option<&PublicKey>,
+                 user: Option<&str>)
+    -> bool
+{
+    destinations.iter().any(|dest| {
+        let from_ok = match from {
+            None => dest.from.hostname.is_none() &&
+                    dest.from.host_keys.is_empty(),
+            Some(key) => hop_has_key(&dest.from, key),
+        };
+        let to_ok = to.map(|key| hop_has_key(&dest.to, key)).unwrap_or(true);
+        let user_ok = match (&dest.to.user, user) {
+            (Some(pattern), Some(user)) => {
+                match_pattern(user.as_bytes(), pattern.as_bytes())
+            }
+            _ => true,
+        };
+        from_ok && to_ok && user_ok
+    })
+}
+
+/// Host certificates aren't supported, so CA keys never match
+fn hop_has_key(hop: &Hop, key: &PublicKey) -> bool {
+    hop.host_keys.iter().any(|k| !k.is_ca && k.key == *key)
+}
+
+/// Parses data of `publickey-hostbound-v00@openssh.com` authentication
+///
+/// Returns user name, session identifier and host key.
+fn parse_userauth_request<'a>(data: &'a [u8], key: &PublicKey)
+    -> Result<(&'a str, &'a [u8], PublicKey), Error>
+{
+    let mut cur = Reader::new(data);
+    let session_id = cur.read_bytes()?;
+    if cur.read_byte()? != SSH2_MSG_USERAUTH_REQUEST {
+        return Err(Error::InvalidFormat);
+    }
+    let user = cur.read_string()?;
+    if cur.read_string()? != "ssh-connection" ||
+        cur.read_string()? != "publickey-hostbound-v00@openssh.com" ||
+        cur.read_byte()? != 1
+    {
+        return Err(Error::InvalidFormat);
+    }
+    let _algorithm = cur.read_string()?;
+    if parse_public_blob(cur.read_bytes()?)? != *key {
+        return Err(Error::KeyMismatch);
+    }
+    let host_key = parse_public_blob(cur.read_bytes()?)?;
+    if !cur.is_empty() {
+        return Err(Error::InvalidFormat);
+    }
+    Ok((user, session_id, host_key))
+}
@@ -0,0 +1,384 @@
+//! Parser of `allowed_signers` files used to verify SSHSIG signatures
+//!
+//! Each line of the file consists of comma-separated principal patterns,
+//! optional comma-separated options and a public key:
+//!
+//! ```text
+//! user@example.com,*@example.org namespaces="git" ssh-ed25519 AAAA...
+//! ```
+//!
+//! Supported options are `cert-authority`, `namespaces="..."`,
+//! `valid-after="..."` and `valid-before="..."`. Timestamps are in
+//! `YYYYMMDD[HHMM[SS]][Z]` format. Like in OpenSSH, timestamps without `Z`
+//! suffix are in local time. Local timezone is only known with `std` feature
+//! on unix systems, otherwise such timestamps are interpreted as UTC. Use
+//! [`AllowedSigners::parse_with_utc_offset`](
+//! struct.AllowedSigners.html#method.parse_with_utc_offset) to specify the
+//! timezone explicitly.
+//!
+//! Entries having `cert-authority` option are parsed but never match plain
+//! keys, as certificates aren't supported yet.
+//!
+//! All times used in this module are unix timestamps (seconds since epoch).
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use openssh::parse_public_key;
+use {PublicKey, Error};
+
+
+/// A parsed `allowed_signers` file
+#[derive(Debug, Clone)]
+pub struct AllowedSigners {
+    entries: Vec<Entry>,
+}
+
+/// Timezone of the timestamps without `Z` suffix
+#[derive(Debug, Clone, Copy)]
+enum LocalTime {
+    System,
+    Offset(i64),
+}
+
+/// Single line of `allowed_signers` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Principal patterns (may contain `*`, `?` and `!` negation)
+    pub principals: Vec<String>,
+    /// Entry is a certificate authority rather than a signing key
+    pub cert_authority: bool,
+    /// Namespace patterns signature is allowed for, `None` means any
+    pub namespaces: Option<Vec<String>>,
+    /// Key is valid since this time (inclusive)
+    pub valid_after: Option<u64>,
+    /// Key is valid until this time (inclusive)
+    pub valid_before: Option<u64>,
+    /// The public key
+    pub key: PublicKey,
+}
+
+impl AllowedSigners {
+    /// Parse `allowed_signers` file contents
+    ///
+    /// Empty lines and comments are skipped. Lines with key types which are
+    /// not supported by this library are also skipped (like `ssh-keygen`
+    /// skips unknown keys), any other error fails the whole file.
+    pub fn parse(data: &str) -> Result<AllowedSigners, Error> {
+        AllowedSigners::parse_with(data, LocalTime::System)
+    }
+    /// Parse `allowed_signers` file contents with the specified timezone
+    ///
+    /// Timestamps without `Z` suffix are interpreted as local time at
+    /// `utc_offset` seconds east of UTC.
+    pub fn parse_with_utc_offset(data: &str, utc_offset: i64)
+        -> Result<AllowedSigners, Error>
+    {
+        AllowedSigners::parse_with(data, LocalTime::Offset(utc_offset))
+    }
+    fn parse_with(data: &str, local: LocalTime)
+        -> Result<AllowedSigners, Error>
+    {
+        let mut entries = Vec::new();
+        for line in data.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match Entry::parse_with(line, local) {
+                Ok(entry) => entries.push(entry),
+                Err(Error::UnsupportedType(..)) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(AllowedSigners { entries: entries })
+    }
+    /// All entries of the file
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+    /// Find principals allowed to sign with the key at the specified time
+    ///
+    /// Returns principal patterns as written in the file, similarly to
+    /// `ssh-keygen -Y find-principals`.
+    pub fn find_principals(&self, key: &PublicKey, time: u64) -> Vec<&str> {
+        self.entries.iter()
+            .filter(|e| !e.cert_authority && &e.key == key && e.valid_at(time))
+            .flat_map(|e| e.principals.iter().map(|p| &p[..]))
+            .collect()
+    }
+    /// Check whether principal is allowed to sign with the key
+    ///
+    /// This is the check `ssh-keygen -Y verify` does after verifying the
+    /// signature itself.
+    pub fn is_allowed(&self, principal: &str, key: &PublicKey,
+                      namespace: &str, time: u64)
+        -> bool
+    {
+        self.entries.iter().any(|e| {
+            !e.cert_authority && &e.key == key && e.valid_at(time) &&
+            match_pattern_list(principal, &e.principals) &&
+            e.namespaces.as_ref()
+                .map(|n| match_pattern_list(namespace, n))
+                .unwrap_or(true)
+        })
+    }
+}
+
+impl Entry {
+    /// Parse a single (non-comment) line of `allowed_signers` file
+    pub fn parse(line: &str) -> Result<Entry, Error> {
+        Entry::parse_with(line, LocalTime::System)
+    }
+    /// Parse a single line with the specified timezone
+    ///
+    /// Timestamps without `Z` suffix are interpreted as local time at
+    /// `utc_offset` seconds east of UTC.
+    pub fn parse_with_utc_offset(line: &str, utc_offset: i64)
+        -> Result<Entry, Error>
+    {
+        Entry::parse_with(line, LocalTime::Offset(utc_offset))
+    }
+    fn parse_with(line: &str, local: LocalTime) -> Result<Entry, Error> {
+        let (principals, rest) = next_token(line.trim())?;
+        if principals.is_empty() {
+            return Err(Error::InvalidFormat);
+        }
+        let mut entry = Entry {
+            principals: principals.split(',').map(|x| x.to_string()).collect(),
+            cert_authority: false,
+            namespaces: None,
+            valid_after: None,
+            valid_before: None,
+            key: PublicKey::Ed25519([0; 32]),  // replaced below
+        };
+        // options are optional, so if the rest of the line parses as a key
+        // there are no options
+        let rest = rest.trim_start();
+        match parse_public_key(rest) {
+            Ok(key) => {
+                entry.key = key;
+                return Ok(entry);
+            }
+            Err(e @ Error::UnsupportedType(..)) => return Err(e),
+            Err(_) => {}
+        }
+        let (options, rest) = parse_options(rest)?;
+        for (name, value) in options {
+            match (&name.to_lowercase()[..], value) {
+                ("cert-authority", None) => entry.cert_authority = true,
+                ("namespaces", Some(val)) => {
+                    entry.namespaces = Some(
+                        val.split(',').map(|x| x.to_string()).collect());
+                }
+                ("valid-after", Some(val)) => {
+                    entry.valid_after = Some(parse_time(&val, local)?);
+                }
+                ("valid-before", Some(val)) => {
+                    entry.valid_before = Some(parse_time(&val, local)?);
+                }
+                _ => return Err(Error::InvalidFormat),
+            }
+        }
+        entry.key = parse_public_key(rest)?;
+        Ok(entry)
+    }
+    /// Returns true if entry is valid at the specified time
+    pub fn valid_at(&self, time: u64) -> bool {
+        self.valid_after.map(|x| time >= x).unwrap_or(true) &&
+        self.valid_before.map(|x| time <= x).unwrap_or(true)
+    }
+}
+
+type Options = Vec<(String, Option<String>)>;
+
+/// Splits first (possibly quoted) whitespace-separated token
+fn next_token(line: &str) -> Result<(String, &str), Error> {
+    if let Some(quoted) = line.strip_prefix('"') {
+        let end = quoted.find('"').ok_or(Error::InvalidFormat)?;
+        Ok((quoted[..end].to_string(), &quoted[end+1..]))
+    } else {
+        let end = line.find(char::is_whitespace).unwrap_or(line.len());
+        Ok((line[..end].to_string(), &line[end..]))
+    }
+}
+
+/// Parses options in the format of `authorized_keys` file
+///
+/// Options are comma-separated `name` or `name="value"` pairs, terminated by
+/// a whitespace. Quotes may be escaped in values by a backslash. Returns
+/// options and the rest of the line.
+pub(crate) fn parse_options(line: &str) -> Result<(Options, &str), Error> {
+    let mut result = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    loop {
+        let mut name = String::new();
+        let mut value = None;
+        while let Some(&(_, c)) = chars.peek() {
+            if c == '=' || c == ',' || c.is_whitespace() {
+                break;
+            }
+            name.push(c);
+            chars.next();
+        }
+        if name.is_empty() {
+            return Err(Error::InvalidFormat);
+        }
+        if let Some(&(_, '=')) = chars.peek() {
+            chars.next();
+            if chars.next().map(|(_, c)| c) != Some('"') {
+                return Err(Error::InvalidFormat);
+            }
+            let mut val = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '\\')) => {
+                        match chars.next() {
+                            Some((_, '"')) => val.push('"'),
+                            Some((_, c)) => { val.push('\\'); val.push(c); }
+                            None => return Err(Error::InvalidFormat),
+                        }
+                    }
+                    Some((_, '"')) => break,
+                    Some((_, c)) => val.push(c),
+                    None => return Err(Error::InvalidFormat),
+                }
+            }
+            value = Some(val);
+        }
+        result.push((name, value));
+        match chars.next() {
+            Some((_, ',')) => continue,
+            Some((idx, c)) if c.is_whitespace() => {
+                return Ok((result, line[idx..].trim_start()));
+            }
+            _ => return Err(Error::InvalidFormat),
+        }
+    }
+}
+
+/// Parses `YYYYMMDD[HHMM[SS]][Z]` into a unix timestamp
+fn parse_time(value: &str, local: LocalTime) -> Result<u64, Error> {
+    let (utc, value) = match value.strip_suffix(&['Z', 'z'][..]) {
+        Some(value) => (true, value),
+        None => (false, value),
+    };
+    if !value.bytes().all(|c| c.is_ascii_digit()) {
+        return Err(Error::InvalidFormat);
+    }
+    let num = |s: &str| s.parse::<u64>().map_err(|_| Error::InvalidFormat);
+    let (hour, minute, second) = match value.len() {
+        8 => (0, 0, 0),
+        12 => (num(&value[8..10])?, num(&value[10..12])?, 0),
+        14 => (num(&value[8..10])?, num(&value[10..12])?,
+               num(&value[12..14])?),
+        _ => return Err(Error::InvalidFormat),
+    };
+    let year = num(&value[..4])?;
+    let month = num(&value[4..6])?;
+    let day = num(&value[6..8])?;
+    if year < 1970 || !(1..=12).contains(&month) ||
+        !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60
+    {
+        return Err(Error::InvalidFormat);
+    }
+    // days from civil, see http://howardhinnant.github.io/date_algorithms.html
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    let time = days * 86400 + hour * 3600 + minute * 60 + second;
+    match local {
+        _ if utc => Ok(time),
+        LocalTime::Offset(offset) => {
+            let time = time as i64 - offset;
+            if time < 0 {
+                return Err(Error::InvalidFormat);
+            }
+            Ok(time as u64)
+        }
+        LocalTime::System => {
+            system_local_time([year, month, day, hour, minute, second], time)
+        }
+    }
+}
+
+/// Converts local time to a unix timestamp
+///
+/// Uses `mktime` the same way as `ssh-keygen` does, so daylight saving time
+/// is taken into account.
+#[cfg(all(feature="std", unix))]
+fn system_local_time(civil: [u64; 6], _utc_time: u64) -> Result<u64, Error> {
+    let [year, month, day, hour, minute, second] = civil;
+    let mut tm: ::libc::tm = unsafe { ::core::mem::zeroed() };
+    tm.tm_year = (year - 1900) as ::libc::c_int;
+    tm.tm_mon = (month - 1) as ::libc::c_int;
+    tm.tm_mday = day as ::libc::c_int;
+    tm.tm_hour = hour as ::libc::c_int;
+    tm.tm_min = minute as ::libc::c_int;
+    tm.tm_sec = second as ::libc::c_int;
+    tm.tm_isdst = -1;
+    let result = unsafe { ::libc::mktime(&mut tm) };
+    if result < 0 {
+        return Err(Error::InvalidFormat);
+    }
+    Ok(result as u64)
+}
+
+/// Local timezone is unknown, so local time is interpreted as UTC
+#[cfg(not(all(feature="std", unix)))]
+fn system_local_time(_civil: [u64; 6], utc_time: u64) -> Result<u64, Error> {
+    Ok(utc_time)
+}
+
+/// Matches a string against a single wildcard pattern (`*` and `?`)
+///
+/// Only the last `*` is backtracked to, so matching takes
+/// `O(value.len() * pattern.len())` time at worst.
+pub(crate) fn match_pattern(value: &[u8], pattern: &[u8]) -> bool {
+    let mut v = 0;
+    let mut p = 0;
+    // pattern position after the last star and value position it matched at
+    let mut star = None;
+    while v < value.len() {
+        match pattern.get(p) {
+            Some(&b'*') => {
+                p += 1;
+                star = Some((p, v));
+            }
+            Some(&c) if c == b'?' || c == value[v] => {
+                p += 1;
+                v += 1;
+            }
+            _ => match star {
+                Some((star_p, star_v)) => {
+                    p = star_p;
+                    v = star_v + 1;
+                    star = Some((star_p, v));
+                }
+                None => return false,
+            },
+        }
+    }
+    return pattern[p..].iter().all(|&c| c == b'*');
+}
+
+/// Matches a string against a list of patterns
+///
+/// Like in OpenSSH, any matching negated pattern (`!pattern`) rejects the
+/// string, otherwise at least one positive pattern must match.
+fn match_pattern_list(value: &str, patterns: &[String]) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if match_pattern(value.as_bytes(), negated.as_bytes()) {
+                return false;
+            }
+        } else if match_pattern(value.as_bytes(), pattern.as_bytes()) {
+            matched = true;
+        }
+    }
+    return matched;
+}
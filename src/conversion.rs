@@ -1,18 +1,161 @@
-use {PrivateKey, PublicKey};
+use core::convert::TryFrom;
+
+use {PrivateKey, PublicKey, PublicKeyRef, EcdsaCurve};
 
 
 impl PrivateKey {
     /// Return public key for this private key
     pub fn public_key(&self) -> PublicKey {
+        self.public_key_ref().to_public_key()
+    }
+    /// Borrow public part of the key as `PublicKeyRef`
+    pub fn public_key_ref(&self) -> PublicKeyRef<'_> {
         match self {
-            &PrivateKey::Ed25519(data) => {
-                let mut ar = [0u8; 32];
-                ar.copy_from_slice(&data[32..]);
-                PublicKey::Ed25519(ar)
+            &PrivateKey::Ed25519(ref data) => {
+                PublicKeyRef::Ed25519(<&[u8; 32]>::try_from(&data[32..])
+                    .expect("ed25519 key is 64 bytes"))
             }
             &PrivateKey::Rsa { ref e, ref n, .. } => {
-                PublicKey::Rsa { exponent: e.clone(), modulus: n.clone() }
+                PublicKeyRef::Rsa { exponent: e, modulus: n }
+            }
+            &PrivateKey::Ecdsa { curve, ref point, .. } => {
+                PublicKeyRef::Ecdsa { curve: curve, point: point }
+            }
+        }
+    }
+    /// Returns true if the public key belongs to this private key
+    ///
+    /// Only public parts of the key are compared, use
+    /// [`validate`](#method.validate) to check that they are consistent
+    /// with the private part.
+    pub fn matches(&self, key: &PublicKey) -> bool {
+        self.public_key_ref() == key.as_key_ref()
+    }
+    /// Size of the key in bits (size of modulus for RSA keys)
+    pub fn bits(&self) -> usize {
+        match *self {
+            PrivateKey::Ed25519(..) => 256,
+            PrivateKey::Rsa { ref n, .. } => rsa_bits(n),
+            PrivateKey::Ecdsa { curve, .. } => curve.bits(),
+        }
+    }
+}
+
+impl PublicKey {
+    /// Size of the key in bits (size of modulus for RSA keys)
+    pub fn bits(&self) -> usize {
+        self.as_key_ref().bits()
+    }
+    /// Borrow key data as `PublicKeyRef`
+    pub fn as_key_ref(&self) -> PublicKeyRef<'_> {
+        match *self {
+            PublicKey::Rsa { ref exponent, ref modulus } => {
+                PublicKeyRef::Rsa { exponent: exponent, modulus: modulus }
+            }
+            PublicKey::Ed25519(ref data) => PublicKeyRef::Ed25519(data),
+            PublicKey::Ecdsa { curve, ref point } => {
+                PublicKeyRef::Ecdsa { curve: curve, point: point }
             }
         }
     }
 }
+
+impl<'a> PublicKeyRef<'a> {
+    /// Size of the key in bits (size of modulus for RSA keys)
+    pub fn bits(&self) -> usize {
+        match *self {
+            PublicKeyRef::Ed25519(..) => 256,
+            PublicKeyRef::Rsa { modulus, .. } => rsa_bits(modulus),
+            PublicKeyRef::Ecdsa { curve, .. } => curve.bits(),
+        }
+    }
+    /// Copy key data into the owned `PublicKey`
+    pub fn to_public_key(self) -> PublicKey {
+        match self {
+            PublicKeyRef::Rsa { exponent, modulus } => PublicKey::Rsa {
+                exponent: exponent.to_vec(),
+                modulus: modulus.to_vec(),
+            },
+            PublicKeyRef::Ed25519(data) => PublicKey::Ed25519(*data),
+            PublicKeyRef::Ecdsa { curve, point } => PublicKey::Ecdsa {
+                curve: curve,
+                point: point.to_vec(),
+            },
+        }
+    }
+}
+
+impl<'a> From<&'a PublicKey> for PublicKeyRef<'a> {
+    fn from(key: &'a PublicKey) -> PublicKeyRef<'a> {
+        key.as_key_ref()
+    }
+}
+
+impl<'a> From<PublicKeyRef<'a>> for PublicKey {
+    fn from(key: PublicKeyRef<'a>) -> PublicKey {
+        key.to_public_key()
+    }
+}
+
+/// Bit length of the big-endian number (mpints may have leading zeros)
+fn rsa_bits(modulus: &[u8]) -> usize {
+    match modulus.iter().position(|&x| x != 0) {
+        Some(idx) => {
+            (modulus.len() - idx) * 8 - modulus[idx].leading_zeros() as usize
+        }
+        None => 0,
+    }
+}
+
+impl EcdsaCurve {
+    /// Curve name as used in openssh keys (e.g. `nistp256`)
+    pub fn name(&self) -> &'static str {
+        match *self {
+            EcdsaCurve::NistP256 => "nistp256",
+            EcdsaCurve::NistP384 => "nistp384",
+            EcdsaCurve::NistP521 => "nistp521",
+        }
+    }
+    /// Key type for this curve (e.g. `ecdsa-sha2-nistp256`)
+    pub fn key_type(&self) -> &'static str {
+        match *self {
+            EcdsaCurve::NistP256 => "ecdsa-sha2-nistp256",
+            EcdsaCurve::NistP384 => "ecdsa-sha2-nistp384",
+            EcdsaCurve::NistP521 => "ecdsa-sha2-nistp521",
+        }
+    }
+    /// Find curve by its name (e.g. `nistp256`)
+    pub fn from_name(name: &str) -> Option<EcdsaCurve> {
+        match name {
+            "nistp256" => Some(EcdsaCurve::NistP256),
+            "nistp384" => Some(EcdsaCurve::NistP384),
+            "nistp521" => Some(EcdsaCurve::NistP521),
+            _ => None,
+        }
+    }
+    /// Find curve by key type (e.g. `ecdsa-sha2-nistp256`)
+    pub fn from_key_type(key_type: &str) -> Option<EcdsaCurve> {
+        match key_type {
+            "ecdsa-sha2-nistp256" => Some(EcdsaCurve::NistP256),
+            "ecdsa-sha2-nistp384" => Some(EcdsaCurve::NistP384),
+            "ecdsa-sha2-nistp521" => Some(EcdsaCurve::NistP521),
+            _ => None,
+        }
+    }
+    /// Size of the curve in bits (e.g. 521 for `nistp521`)
+    pub fn bits(&self) -> usize {
+        match *self {
+            EcdsaCurve::NistP256 => 256,
+            EcdsaCurve::NistP384 => 384,
+            EcdsaCurve::NistP521 => 521,
+        }
+    }
+    /// Size of the field element (and private scalar) in bytes
+    pub fn field_size(&self) -> usize {
+        match *self {
+            EcdsaCurve::NistP256 => 32,
+            EcdsaCurve::NistP384 => 48,
+            EcdsaCurve::NistP521 => 66,
+        }
+    }
+}
@@ -1,32 +1,66 @@
-use std::fmt;
+use core::fmt;
 
-use {PublicKey, PrivateKey};
+use {PublicKey, PublicKeyRef, PrivateKey};
 
+// Keys are printed as algorithm, size and SHA256 fingerprint, so they can be
+// identified in logs without printing the whole key
 impl fmt::Debug for PublicKey {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use PublicKey::*;
         match *self {
             Rsa { .. } => {
-                // TODO(tailhook) show length
-                write!(f, "PublicKey::Rsa")
+                write!(f, "PublicKey::Rsa({} bits, {})",
+                       self.bits(), self.fingerprint())
             }
             Ed25519(..) => {
-                write!(f, "PublicKey::Ed25519")
+                write!(f, "PublicKey::Ed25519({} bits, {})",
+                       self.bits(), self.fingerprint())
+            }
+            Ecdsa { curve, .. } => {
+                write!(f, "PublicKey::Ecdsa({}, {} bits, {})",
+                       curve.name(), self.bits(), self.fingerprint())
             }
         }
     }
 }
 
+impl<'a> fmt::Debug for PublicKeyRef<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use PublicKeyRef::*;
+        match *self {
+            Rsa { .. } => {
+                write!(f, "PublicKeyRef::Rsa({} bits, {})",
+                       self.bits(), self.fingerprint())
+            }
+            Ed25519(..) => {
+                write!(f, "PublicKeyRef::Ed25519({} bits, {})",
+                       self.bits(), self.fingerprint())
+            }
+            Ecdsa { curve, .. } => {
+                write!(f, "PublicKeyRef::Ecdsa({}, {} bits, {})",
+                       curve.name(), self.bits(), self.fingerprint())
+            }
+        }
+    }
+}
+
+// Secret fields are never printed, only the fingerprint of the public part
 impl fmt::Debug for PrivateKey {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use PrivateKey::*;
+        let fingerprint = self.public_key_ref().fingerprint();
         match *self {
             Rsa { .. } => {
-                // TODO(tailhook) show length
-                write!(f, "PrivateKey::Rsa")
+                write!(f, "PrivateKey::Rsa({} bits, {})",
+                       self.bits(), fingerprint)
             }
             Ed25519(..) => {
-                write!(f, "PrivateKey::Ed25519")
+                write!(f, "PrivateKey::Ed25519({} bits, {})",
+                       self.bits(), fingerprint)
+            }
+            Ecdsa { curve, .. } => {
+                write!(f, "PrivateKey::Ecdsa({}, {} bits, {})",
+                       curve.name(), self.bits(), fingerprint)
             }
         }
     }
@@ -1,21 +1,131 @@
-quick_error! {
-    /// Key parsing error
-    #[derive(Debug)]
-    pub enum Error {
-        /// This error usually means that file is damaged
-        InvalidFormat {
-            description("invalid key format")
-        }
-        /// Unsupported key type
-        UnsupportedType(typ: String) {
-            description("unsupported key type")
-            display("unsupported key type {:?}", typ)
+use core::fmt;
+use alloc::string::String;
+#[cfg(feature="std")]
+use std::error::Error as StdError;
+#[cfg(feature="std")]
+use std::io;
+
+use openssh::Section;
+
+
+/// Key parsing error
+#[derive(Debug)]
+pub enum Error {
+    /// This error usually means that file is damaged
+    InvalidFormat,
+    /// Key data ends before the value at `offset`
+    #[allow(missing_docs)]
+    Truncated { section: Section, offset: usize },
+    /// Unexpected value in key data
+    #[allow(missing_docs)]
+    Unexpected { section: Section, offset: usize,
+                 expected: String, found: String },
+    /// Extra data after the end of the key (rejected in strict mode)
+    #[allow(missing_docs)]
+    TrailingData { section: Section, offset: usize },
+    /// Unsupported key type
+    UnsupportedType(String),
+    /// Private key was encrypted (we don't support encrypted keys yet)
+    Encrypted,
+    /// Key material is rejected by the cryptographic primitive
+    InvalidKey,
+    /// Signature algorithm can't be used with this kind of key
+    IncompatibleAlgorithm(&'static str),
+    /// Signature verification failed
+    InvalidSignature,
+    /// Signature uses algorithm that is considered insecure
+    InsecureAlgorithm(&'static str),
+    /// Signature was made by a different key
+    KeyMismatch,
+    /// Signature was made for a different namespace
+    NamespaceMismatch,
+    /// I/O error (e.g. when talking to ssh-agent)
+    #[cfg(feature="std")]
+    Io(io::Error),
+    /// Agent responded with failure
+    AgentFailure,
+    /// RSA modulus is not a product of the primes `p` and `q`
+    ModulusMismatch,
+    /// RSA private exponent is not an inverse of public exponent
+    PrivateExponentMismatch,
+    /// RSA CRT coefficient is not an inverse of `q` modulo `p`
+    CoefficientMismatch,
+    /// Public half stored with private key doesn't match the private key
+    PublicKeyMismatch,
+    /// Private key file is readable by group or others
+    InsecurePermissions,
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Error::*;
+        match *self {
+            InvalidFormat => f.write_str("invalid key format"),
+            Truncated { section, offset } => {
+                write!(f, "key data is truncated: {} at byte {}",
+                       section, offset)
+            }
+            Unexpected { section, offset, ref expected, ref found } => {
+                write!(f, "invalid {} at byte {}: expected {}, found {}",
+                       section, offset, expected, found)
+            }
+            TrailingData { section, offset } => {
+                write!(f, "trailing data in {} at byte {}", section, offset)
+            }
+            UnsupportedType(ref typ) => {
+                write!(f, "unsupported key type {:?}", typ)
+            }
+            Encrypted => f.write_str("key was encrypted"),
+            InvalidKey => f.write_str("invalid key"),
+            IncompatibleAlgorithm(alg) => {
+                write!(f, "signature algorithm {:?} is incompatible \
+                           with the key", alg)
+            }
+            InvalidSignature => f.write_str("invalid signature"),
+            InsecureAlgorithm(alg) => {
+                write!(f, "insecure signature algorithm {:?}", alg)
+            }
+            KeyMismatch => {
+                f.write_str("signature was made by a different key")
+            }
+            NamespaceMismatch => f.write_str("signature namespace mismatch"),
+            #[cfg(feature="std")]
+            Io(ref err) => write!(f, "I/O error: {}", err),
+            AgentFailure => f.write_str("agent refused the operation"),
+            ModulusMismatch => f.write_str("RSA modulus doesn't match primes"),
+            PrivateExponentMismatch => {
+                f.write_str("RSA private exponent doesn't match \
+                             public exponent")
+            }
+            CoefficientMismatch => {
+                f.write_str("RSA CRT coefficient is invalid")
+            }
+            PublicKeyMismatch => {
+                f.write_str("public key doesn't match private key")
+            }
+            InsecurePermissions => {
+                f.write_str("private key file is accessible by others")
+            }
+            __Nonexhaustive => f.write_str("unknown error"),
         }
-        /// Private key was encrypted (we don't support encrypted keys yet)
-        Encrypted {
-            description("key was encrypted")
+    }
+}
+
+#[cfg(feature="std")]
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            Error::Io(ref err) => Some(err),
+            _ => None,
         }
-        #[doc(hidden)]
-        __Nonexhaustive
+    }
+}
+
+#[cfg(feature="std")]
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
     }
 }
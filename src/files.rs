@@ -0,0 +1,181 @@
+//! Reading keys from files and `~/.ssh` directory
+//!
+//! [`scan_ssh_dir`](fn.scan_ssh_dir.html) finds keys the same way as
+//! `ssh` finds default identities: the private key is stored in `id_*` file,
+//! the public key is stored next to it with `.pub` suffix and the
+//! certificate (if any) with `-cert.pub` suffix:
+//!
+//! ```rust,ignore
+//! for files in files::scan_ssh_dir("/home/user/.ssh")? {
+//!     if files.insecure {
+//!         eprintln!("Warning: {} is readable by others", files.name);
+//!         continue;
+//!     }
+//!     let keys = files.private_keys()?;
+//! }
+//! ```
+use std::collections::BTreeMap;
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+use zeroize::Zeroizing;
+
+use openssh::{parse_public_key, parse_private_key, parse_key_file};
+use {PublicKey, PrivateKey, Error};
+
+
+/// Set of files belonging to a single key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyFiles {
+    /// Name of the key (private key file name, e.g. `id_ed25519`)
+    pub name: String,
+    /// Private key file (`id_ed25519`)
+    pub private_key: Option<PathBuf>,
+    /// Public key file (`id_ed25519.pub`)
+    pub public_key: Option<PathBuf>,
+    /// Certificate file (`id_ed25519-cert.pub`), certificates aren't parsed
+    /// by this library
+    pub certificate: Option<PathBuf>,
+    /// Private key file is readable by group or others
+    ///
+    /// OpenSSH refuses to use such keys, and so does
+    /// [`private_keys`](#method.private_keys).
+    pub insecure: bool,
+}
+
+impl PrivateKey {
+    /// Read private keys from the file in openssh format
+    ///
+    /// Like OpenSSH, refuses to read the file if it's accessible by group
+    /// or others (`Error::InsecurePermissions`).
+    pub fn from_file<P: AsRef<Path>>(path: P)
+        -> Result<Vec<PrivateKey>, Error>
+    {
+        parse_private_key(&read_private_file(path.as_ref())?)
+    }
+}
+
+impl PublicKey {
+    /// Read public key from the file in openssh format (e.g. `id_rsa.pub`)
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<PublicKey, Error> {
+        let mut buf = String::with_capacity(1024);
+        File::open(path)?.read_to_string(&mut buf)?;
+        parse_public_key(&buf)
+    }
+}
+
+impl KeyFiles {
+    fn new(name: &str) -> KeyFiles {
+        KeyFiles {
+            name: name.to_string(),
+            private_key: None,
+            public_key: None,
+            certificate: None,
+            insecure: false,
+        }
+    }
+    fn read_private_key_file(&self) -> Result<Zeroizing<String>, Error> {
+        let path = self.private_key.as_ref().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "no private key file")
+        })?;
+        if self.insecure {
+            return Err(Error::InsecurePermissions);
+        }
+        return read_private_file(path);
+    }
+    /// Read private keys
+    ///
+    /// Returns `Error::InsecurePermissions` if the file is readable by
+    /// group or others.
+    pub fn private_keys(&self) -> Result<Vec<PrivateKey>, Error> {
+        parse_private_key(&self.read_private_key_file()?)
+    }
+    /// Read public key
+    ///
+    /// If there is no `.pub` file, public key is read from the private
+    /// key file (which is also checked for permissions). This works for
+    /// encrypted `openssh-key-v1` files too.
+    pub fn public_key(&self) -> Result<PublicKey, Error> {
+        if let Some(ref path) = self.public_key {
+            return PublicKey::from_file(path);
+        }
+        let data = self.read_private_key_file()?;
+        let key = match parse_key_file(&data) {
+            Ok(file) => file.public_keys.into_iter().next(),
+            Err(_) => parse_private_key(&data)?.first()
+                .map(|key| key.public_key()),
+        };
+        return key.ok_or(Error::InvalidFormat);
+    }
+}
+
+/// Reads the file if it isn't accessible by group or others
+///
+/// Buffer is allocated for the whole file upfront, so that no copies of
+/// the key are left behind when it grows.
+fn read_private_file(path: &Path) -> Result<Zeroizing<String>, Error> {
+    let mut file = File::open(path)?;
+    let meta = file.metadata()?;
+    if is_insecure(&meta) {
+        return Err(Error::InsecurePermissions);
+    }
+    let mut buf = Zeroizing::new(String::with_capacity(meta.len() as usize));
+    file.read_to_string(&mut buf)?;
+    return Ok(buf);
+}
+
+#[cfg(unix)]
+fn is_insecure(meta: &Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o077 != 0
+}
+
+#[cfg(not(unix))]
+fn is_insecure(_meta: &Metadata) -> bool {
+    false
+}
+
+/// Find keys in the ssh directory (usually `~/.ssh`)
+///
+/// Private keys are `id_*` files, public keys and certificates are matched
+/// to them by the name. Public keys and certificates without a private key
+/// are returned too (their `private_key` is `None`).
+///
+/// Keys are sorted by name. Unreadable directory is an error, but
+/// group- or world-readable private keys are only marked as `insecure`.
+pub fn scan_ssh_dir<P: AsRef<Path>>(path: P) -> Result<Vec<KeyFiles>, Error> {
+    let mut keys = BTreeMap::new();
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let path = entry.path();
+        // follows symlinks, so linked keys are found too
+        let meta = match fs::metadata(&path) {
+            Ok(meta) if meta.is_file() => meta,
+            _ => continue,
+        };
+        let file_name = entry.file_name();
+        let file_name = match file_name.to_str() {
+            Some(name) => name,
+            None => continue,
+        };
+        if let Some(name) = file_name.strip_suffix("-cert.pub") {
+            keys.entry(name.to_string())
+                .or_insert_with(|| KeyFiles::new(name))
+                .certificate = Some(path);
+        } else if let Some(name) = file_name.strip_suffix(".pub") {
+            keys.entry(name.to_string())
+                .or_insert_with(|| KeyFiles::new(name))
+                .public_key = Some(path);
+        } else if file_name.starts_with("id_") {
+            let insecure = is_insecure(&meta);
+            let files = keys.entry(file_name.to_string())
+                .or_insert_with(|| KeyFiles::new(file_name));
+            files.private_key = Some(path);
+            files.insecure = insecure;
+        }
+    }
+    return Ok(keys.into_values().collect());
+}
@@ -0,0 +1,113 @@
+use core::fmt;
+use core::str::FromStr;
+
+use base64ct::{Base64Unpadded, Encoding};
+use sha1::Sha1;
+use sha2::{Sha256, Digest};
+use sha2::digest::Output;
+
+use {PublicKey, PublicKeyRef, Error};
+
+
+/// Fingerprint (hash of the wire-format blob) of a public key
+///
+/// Displayed in the same format as `ssh-keygen -l` prints it, e.g.
+/// `SHA256:nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Fingerprint {
+    /// Legacy SHA-1 fingerprint
+    Sha1([u8; 20]),
+    /// SHA-256 fingerprint (the default in OpenSSH)
+    Sha256([u8; 32]),
+}
+
+impl Fingerprint {
+    /// Parse fingerprint in `SHA256:base64` or `SHA1:base64` format
+    pub fn parse(value: &str) -> Result<Fingerprint, Error> {
+        let (kind, data) = if let Some(data) = value.strip_prefix("SHA256:") {
+            ("SHA256", data)
+        } else if let Some(data) = value.strip_prefix("SHA1:") {
+            ("SHA1", data)
+        } else {
+            return Err(Error::InvalidFormat);
+        };
+        let data = Base64Unpadded::decode_vec(data.trim_end_matches('='))
+            .map_err(|_| Error::InvalidFormat)?;
+        Fingerprint::from_bytes(kind, &data)
+    }
+    fn from_bytes(kind: &str, data: &[u8]) -> Result<Fingerprint, Error> {
+        match (kind, data.len()) {
+            ("SHA1", 20) => {
+                let mut hash = [0u8; 20];
+                hash.copy_from_slice(data);
+                Ok(Fingerprint::Sha1(hash))
+            }
+            ("SHA256", 32) => {
+                let mut hash = [0u8; 32];
+                hash.copy_from_slice(data);
+                Ok(Fingerprint::Sha256(hash))
+            }
+            _ => Err(Error::InvalidFormat),
+        }
+    }
+    /// Raw hash value
+    pub fn as_bytes(&self) -> &[u8] {
+        match *self {
+            Fingerprint::Sha1(ref hash) => hash,
+            Fingerprint::Sha256(ref hash) => hash,
+        }
+    }
+}
+
+impl FromStr for Fingerprint {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Fingerprint, Error> {
+        Fingerprint::parse(s)
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match *self {
+            Fingerprint::Sha1(..) => "SHA1",
+            Fingerprint::Sha256(..) => "SHA256",
+        };
+        write!(f, "{}:{}", kind,
+            Base64Unpadded::encode_string(self.as_bytes()))
+    }
+}
+
+impl PublicKey {
+    /// SHA-256 fingerprint of the key
+    pub fn fingerprint(&self) -> Fingerprint {
+        self.as_key_ref().fingerprint()
+    }
+    /// Legacy SHA-1 fingerprint of the key
+    pub fn fingerprint_sha1(&self) -> Fingerprint {
+        self.as_key_ref().fingerprint_sha1()
+    }
+}
+
+impl<'a> PublicKeyRef<'a> {
+    /// SHA-256 fingerprint of the key
+    pub fn fingerprint(&self) -> Fingerprint {
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&self.blob_digest::<Sha256>());
+        Fingerprint::Sha256(hash)
+    }
+    /// Legacy SHA-1 fingerprint of the key
+    pub fn fingerprint_sha1(&self) -> Fingerprint {
+        let mut hash = [0u8; 20];
+        hash.copy_from_slice(&self.blob_digest::<Sha1>());
+        Fingerprint::Sha1(hash)
+    }
+    // hashes blob without building it in memory
+    fn blob_digest<D: Digest>(&self) -> Output<D> {
+        let mut hasher = D::new();
+        self.blob_fields(|field| {
+            hasher.update((field.len() as u32).to_be_bytes());
+            hasher.update(field);
+        });
+        hasher.finalize()
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::BufRead;
+use std::string::{String, ToString};
+use std::vec::Vec;
+use std::str::from_utf8;
+
+use allowed_signers::parse_options;
+use openssh::{ParseOptions, Section};
+use {PublicKey, Error};
+
+
+/// Streaming reader of public keys
+///
+/// Reads files in `authorized_keys` format (or concatenated `.pub` files)
+/// line by line, and yields `(line_number, key, comment)` for each key.
+/// Line numbers start from one. Empty lines and comments are skipped. Options
+/// which `authorized_keys` lines can start with are skipped too.
+///
+/// Errors in a line don't stop iteration, reading continues with the next
+/// line. Use [`line_number`](#method.line_number) to find out which line
+/// the error is in.
+///
+/// ```rust,ignore
+/// let file = BufReader::new(File::open("keys.txt")?);
+/// for item in PublicKeyReader::new(file) {
+///     let (line_no, key, comment) = item?;
+///     println!("{}: {} {}", line_no, key.fingerprint(), comment);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PublicKeyReader<R> {
+    input: R,
+    options: ParseOptions,
+    line: Vec<u8>,
+    buf: Vec<u8>,
+    line_no: usize,
+}
+
+impl<R: BufRead> PublicKeyReader<R> {
+    /// Create a reader which parses keys leniently
+    pub fn new(input: R) -> PublicKeyReader<R> {
+        PublicKeyReader::with_options(input, &ParseOptions::new())
+    }
+    /// Create a reader which parses keys with the specified options
+    pub fn with_options(input: R, options: &ParseOptions)
+        -> PublicKeyReader<R>
+    {
+        PublicKeyReader {
+            input: input,
+            options: options.clone(),
+            line: Vec::with_capacity(1024),
+            buf: Vec::with_capacity(1024),
+            line_no: 0,
+        }
+    }
+    /// Number of the last line read
+    pub fn line_number(&self) -> usize {
+        self.line_no
+    }
+    /// Returns the underlying reader
+    pub fn into_inner(self) -> R {
+        self.input
+    }
+    fn parse_line(&mut self) -> Option<Result<(PublicKey, String), Error>> {
+        let line = match from_utf8(&self.line) {
+            Ok(line) => line.trim(),
+            Err(e) => return Some(Err(Error::Unexpected {
+                section: Section::Armor,
+                offset: e.valid_up_to(),
+                expected: "UTF-8 text".into(),
+                found: "invalid UTF-8".into(),
+            })),
+        };
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let err = match self.options.parse_public_key_buf(line, &mut self.buf)
+        {
+            Ok((key, comment)) => {
+                return Some(Ok((key.to_public_key(),
+                                comment.to_string())));
+            }
+            Err(e @ Error::UnsupportedType(..)) => return Some(Err(e)),
+            Err(e) => e,
+        };
+        // authorized_keys line may start with options
+        if let Ok((_, rest)) = parse_options(line) {
+            let rest = rest.trim_start();
+            if let Ok((key, comment)) =
+                self.options.parse_public_key_buf(rest, &mut self.buf)
+            {
+                return Some(Ok((key.to_public_key(),
+                                comment.to_string())));
+            }
+        }
+        return Some(Err(err));
+    }
+}
+
+impl<R: BufRead> Iterator for PublicKeyReader<R> {
+    type Item = Result<(usize, PublicKey, String), Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            match self.input.read_until(b'\n', &mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => self.line_no += 1,
+                Err(e) => return Some(Err(e.into())),
+            }
+            match self.parse_line() {
+                Some(Ok((key, comment))) => {
+                    return Some(Ok((self.line_no, key, comment)));
+                }
+                Some(Err(e)) => return Some(Err(e)),
+                None => continue,
+            }
+        }
+    }
+}
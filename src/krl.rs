@@ -0,0 +1,686 @@
+//! OpenSSH key revocation lists (KRL)
+//!
+//! KRLs are produced by `ssh-keygen -k` and consumed by `RevokedKeys`
+//! option of sshd. The binary format is described in `PROTOCOL.krl` file
+//! of OpenSSH.
+//!
+//! Certificates aren't parsed by this library, so certificate checks take
+//! the CA key, serial and key ID of the certificate explicitly.
+//!
+//! KRLs can be generated with [`KrlBuilder`](struct.KrlBuilder.html), which
+//! produces the same output as `ssh-keygen -k` does.
+use std::cmp::min;
+use std::string::{String, ToString};
+use std::vec::Vec;
+use std::collections::BTreeSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use openssh::{parse_public_blob, parse_public_key};
+use wire::{Reader, write_string, write_mpint};
+use {PublicKey, PrivateKey, Fingerprint, Signature, Error};
+
+
+const KRL_MAGIC: &[u8] = b"SSHKRL\n\0";
+const KRL_FORMAT_VERSION: u32 = 1;
+
+const KRL_SECTION_CERTIFICATES: u8 = 1;
+const KRL_SECTION_EXPLICIT_KEY: u8 = 2;
+const KRL_SECTION_FINGERPRINT_SHA1: u8 = 3;
+const KRL_SECTION_SIGNATURE: u8 = 4;
+const KRL_SECTION_FINGERPRINT_SHA256: u8 = 5;
+
+const KRL_SECTION_CERT_SERIAL_LIST: u8 = 0x20;
+const KRL_SECTION_CERT_SERIAL_RANGE: u8 = 0x21;
+const KRL_SECTION_CERT_SERIAL_BITMAP: u8 = 0x22;
+const KRL_SECTION_CERT_KEY_ID: u8 = 0x23;
+
+
+/// A parsed key revocation list
+#[derive(Debug, Clone)]
+pub struct Krl {
+    version: u64,
+    generated_date: u64,
+    comment: String,
+    certificates: Vec<RevokedCertificates>,
+    keys: Vec<PublicKey>,
+    fingerprints: Vec<Fingerprint>,
+    signing_keys: Vec<PublicKey>,
+}
+
+/// Builder of binary KRLs
+///
+/// ```rust,ignore
+/// let krl = KrlBuilder::new()
+///     .version(2)
+///     .add_spec(&spec, Some(&ca))?
+///     .build();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct KrlBuilder {
+    version: u64,
+    generated_date: Option<u64>,
+    comment: String,
+    certificates: Vec<RevokedCertificates>,
+    keys: BTreeSet<Vec<u8>>,
+    sha1: BTreeSet<Vec<u8>>,
+    sha256: BTreeSet<Vec<u8>>,
+}
+
+/// Certificates revoked for a single certificate authority
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RevokedCertificates {
+    /// CA key, `None` means revocations apply to certificates of any CA
+    pub ca: Option<PublicKey>,
+    /// Ranges of revoked serial numbers (both ends inclusive)
+    pub serials: Vec<(u64, u64)>,
+    /// Revoked key IDs
+    pub key_ids: Vec<String>,
+}
+
+impl Krl {
+    /// Parse binary KRL
+    ///
+    /// All signatures the KRL contains are verified, but it's up to the
+    /// caller to check whether [`signing_keys`](#method.signing_keys) are
+    /// trusted. Keys of types unsupported by this library are skipped as
+    /// they can't match any key anyway.
+    pub fn parse(data: &[u8]) -> Result<Krl, Error> {
+        if !data.starts_with(KRL_MAGIC) {
+            return Err(Error::InvalidFormat);
+        }
+        let mut cur = Reader::new(&data[KRL_MAGIC.len()..]);
+        if cur.read_u32()? != KRL_FORMAT_VERSION {
+            return Err(Error::InvalidFormat);
+        }
+        let mut krl = Krl {
+            version: cur.read_u64()?,
+            generated_date: cur.read_u64()?,
+            comment: String::new(),
+            certificates: Vec::new(),
+            keys: Vec::new(),
+            fingerprints: Vec::new(),
+            signing_keys: Vec::new(),
+        };
+        let _flags = cur.read_u64()?;
+        let _reserved = cur.read_bytes()?;
+        krl.comment = cur.read_string()?.to_string();
+        while !cur.is_empty() {
+            let section = cur.read_byte()?;
+            let body = cur.read_bytes()?;
+            if section == KRL_SECTION_SIGNATURE {
+                // signature covers everything up to the signing key
+                let signed = &data[..KRL_MAGIC.len() + cur.position()];
+                let key = parse_public_blob(body)?;
+                let signature = Signature::from_bytes(cur.read_bytes()?)?;
+                key.verify(signed, &signature)?;
+                krl.signing_keys.push(key);
+                continue;
+            }
+            if !krl.signing_keys.is_empty() {
+                // only signatures may follow signatures
+                return Err(Error::InvalidFormat);
+            }
+            let mut body = Reader::new(body);
+            match section {
+                KRL_SECTION_CERTIFICATES => {
+                    if let Some(certs) = parse_certificates(&mut body)? {
+                        krl.certificates.push(certs);
+                    }
+                }
+                KRL_SECTION_EXPLICIT_KEY => {
+                    while !body.is_empty() {
+                        match parse_public_blob(body.read_bytes()?) {
+                            Ok(key) => krl.keys.push(key),
+                            Err(Error::UnsupportedType(..)) => {}
+                            Err(e) => return Err(e),
+                        }
+                    }
+                }
+                KRL_SECTION_FINGERPRINT_SHA1 => {
+                    while !body.is_empty() {
+                        let hash = body.read_bytes()?;
+                        if hash.len() != 20 {
+                            return Err(Error::InvalidFormat);
+                        }
+                        let mut value = [0u8; 20];
+                        value.copy_from_slice(hash);
+                        krl.fingerprints.push(Fingerprint::Sha1(value));
+                    }
+                }
+                KRL_SECTION_FINGERPRINT_SHA256 => {
+                    while !body.is_empty() {
+                        let hash = body.read_bytes()?;
+                        if hash.len() != 32 {
+                            return Err(Error::InvalidFormat);
+                        }
+                        let mut value = [0u8; 32];
+                        value.copy_from_slice(hash);
+                        krl.fingerprints.push(Fingerprint::Sha256(value));
+                    }
+                }
+                _ => return Err(Error::InvalidFormat),
+            }
+        }
+        Ok(krl)
+    }
+    /// Version (sequence number) of the KRL
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+    /// Unix timestamp of the KRL generation
+    pub fn generated_date(&self) -> u64 {
+        self.generated_date
+    }
+    /// Free-form comment
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+    /// Certificate revocations grouped by CA
+    pub fn certificates(&self) -> &[RevokedCertificates] {
+        &self.certificates
+    }
+    /// Explicitly revoked keys
+    pub fn revoked_keys(&self) -> &[PublicKey] {
+        &self.keys
+    }
+    /// Keys revoked by SHA1 or SHA256 fingerprint
+    pub fn revoked_fingerprints(&self) -> &[Fingerprint] {
+        &self.fingerprints
+    }
+    /// Keys that signed the KRL (signatures are already verified)
+    pub fn signing_keys(&self) -> &[PublicKey] {
+        &self.signing_keys
+    }
+    /// Returns true if plain public key is revoked
+    pub fn is_revoked(&self, key: &PublicKey) -> bool {
+        if self.keys.contains(key) {
+            return true;
+        }
+        if self.fingerprints.is_empty() {
+            return false;
+        }
+        let sha1 = key.fingerprint_sha1();
+        let sha256 = key.fingerprint();
+        self.fingerprints.iter().any(|f| *f == sha1 || *f == sha256)
+    }
+    /// Returns true if certificate is revoked
+    ///
+    /// Certificate is revoked if either its key or its CA key is revoked, or
+    /// if its serial or key ID is revoked for this CA. Zero serial means
+    /// certificate has no serial, so it's never revoked by serial.
+    pub fn is_certificate_revoked(&self, key: &PublicKey, ca: &PublicKey,
+                                  serial: u64, key_id: &str)
+        -> bool
+    {
+        self.is_revoked(key) || self.is_revoked(ca) ||
+        self.certificates.iter().any(|certs| {
+            certs.ca.as_ref().map(|c| c == ca).unwrap_or(true) &&
+            certs.is_revoked(serial, key_id)
+        })
+    }
+}
+
+impl RevokedCertificates {
+    /// Returns true if serial or key ID is revoked
+    pub fn is_revoked(&self, serial: u64, key_id: &str) -> bool {
+        self.key_ids.iter().any(|id| id == key_id) ||
+        serial != 0 &&
+            self.serials.iter().any(|&(lo, hi)| lo <= serial && serial <= hi)
+    }
+}
+
+/// Parses certificates section, returns `None` for unsupported CA types
+fn parse_certificates(cur: &mut Reader)
+    -> Result<Option<RevokedCertificates>, Error>
+{
+    let ca_blob = cur.read_bytes()?;
+    let _reserved = cur.read_bytes()?;
+    let mut certs = RevokedCertificates {
+        ca: None,
+        serials: Vec::new(),
+        key_ids: Vec::new(),
+    };
+    let mut supported = true;
+    if !ca_blob.is_empty() {
+        match parse_public_blob(ca_blob) {
+            Ok(key) => certs.ca = Some(key),
+            Err(Error::UnsupportedType(..)) => supported = false,
+            Err(e) => return Err(e),
+        }
+    }
+    while !cur.is_empty() {
+        let kind = cur.read_byte()?;
+        let mut sub = Reader::new(cur.read_bytes()?);
+        match kind {
+            KRL_SECTION_CERT_SERIAL_LIST => {
+                while !sub.is_empty() {
+                    let serial = sub.read_u64()?;
+                    certs.serials.push(check_range(serial, serial)?);
+                }
+            }
+            KRL_SECTION_CERT_SERIAL_RANGE => {
+                let lo = sub.read_u64()?;
+                let hi = sub.read_u64()?;
+                certs.serials.push(check_range(lo, hi)?);
+            }
+            KRL_SECTION_CERT_SERIAL_BITMAP => {
+                let offset = sub.read_u64()?;
+                let bitmap = sub.read_mpint_strict()?;
+                let bit = |i: usize| {
+                    bitmap[bitmap.len() - 1 - i / 8] >> (i % 8) & 1 == 1
+                };
+                let mut start = None;
+                for i in 0..bitmap.len()*8 + 1 {
+                    let set = i < bitmap.len()*8 && bit(i);
+                    match (start, set) {
+                        (None, true) => start = Some(i),
+                        (Some(first), false) => {
+                            let serial = |i: usize| offset.checked_add(i as u64)
+                                .ok_or(Error::InvalidFormat);
+                            certs.serials.push(
+                                check_range(serial(first)?, serial(i - 1)?)?);
+                            start = None;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            KRL_SECTION_CERT_KEY_ID => {
+                while !sub.is_empty() {
+                    certs.key_ids.push(sub.read_string()?.to_string());
+                }
+            }
+            _ => return Err(Error::InvalidFormat),
+        }
+        if !sub.is_empty() {
+            return Err(Error::InvalidFormat);
+        }
+    }
+    if supported {
+        Ok(Some(certs))
+    } else {
+        Ok(None)
+    }
+}
+
+fn check_range(lo: u64, hi: u64) -> Result<(u64, u64), Error> {
+    if lo == 0 || lo > hi {
+        return Err(Error::InvalidFormat);
+    }
+    Ok((lo, hi))
+}
+
+impl KrlBuilder {
+    /// Create a builder of an empty KRL
+    pub fn new() -> KrlBuilder {
+        KrlBuilder::default()
+    }
+    /// Set version (sequence number) of the KRL, zero by default
+    pub fn version(&mut self, version: u64) -> &mut Self {
+        self.version = version;
+        self
+    }
+    /// Set generation time (unix timestamp), current time by default
+    pub fn generated_date(&mut self, time: u64) -> &mut Self {
+        self.generated_date = Some(time);
+        self
+    }
+    /// Set free-form comment
+    pub fn comment(&mut self, comment: &str) -> &mut Self {
+        self.comment = comment.to_string();
+        self
+    }
+    /// Revoke the key explicitly
+    pub fn revoke_key(&mut self, key: &PublicKey) -> &mut Self {
+        let mut blob = Vec::with_capacity(512);
+        key.write_blob(&mut blob);
+        self.keys.insert(blob);
+        self
+    }
+    /// Revoke the key by its fingerprint
+    pub fn revoke_fingerprint(&mut self, fingerprint: Fingerprint)
+        -> &mut Self
+    {
+        match fingerprint {
+            Fingerprint::Sha1(hash) => self.sha1.insert(hash.to_vec()),
+            Fingerprint::Sha256(hash) => self.sha256.insert(hash.to_vec()),
+        };
+        self
+    }
+    /// Revoke certificate by serial number
+    ///
+    /// `None` as a CA means certificates of any CA. Zero serial is invalid.
+    pub fn revoke_serial(&mut self, ca: Option<&PublicKey>, serial: u64)
+        -> Result<&mut Self, Error>
+    {
+        self.revoke_serial_range(ca, serial, serial)
+    }
+    /// Revoke certificates by range of serial numbers (both inclusive)
+    pub fn revoke_serial_range(&mut self, ca: Option<&PublicKey>,
+                               lo: u64, hi: u64)
+        -> Result<&mut Self, Error>
+    {
+        check_range(lo, hi)?;
+        let serials = &mut self.certificates_for(ca).serials;
+        serials.push((lo, hi));
+        serials.sort();
+        // merge overlapping and adjacent ranges
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(serials.len());
+        for &(lo, hi) in serials.iter() {
+            match merged.last_mut() {
+                Some(last) if lo <= last.1.saturating_add(1) => {
+                    last.1 = last.1.max(hi);
+                }
+                _ => merged.push((lo, hi)),
+            }
+        }
+        *serials = merged;
+        Ok(self)
+    }
+    /// Revoke certificates by key ID
+    ///
+    /// `None` as a CA means certificates of any CA.
+    pub fn revoke_key_id(&mut self, ca: Option<&PublicKey>, key_id: &str)
+        -> &mut Self
+    {
+        let key_ids = &mut self.certificates_for(ca).key_ids;
+        if let Err(idx) = key_ids.binary_search_by(|x| x[..].cmp(key_id)) {
+            key_ids.insert(idx, key_id.to_string());
+        }
+        self
+    }
+    /// Add revocations from the text specification of `ssh-keygen -k`
+    ///
+    /// Each line is one of:
+    ///
+    /// * `serial: <serial>[-<serial>]` -- revoke certificates by serial
+    /// * `id: <key id>` -- revoke certificates by key ID
+    /// * `key: <public key>` or just `<public key>` -- revoke key explicitly
+    /// * `sha1: <public key>`, `sha256: <public key>` -- revoke key by hash
+    /// * `hash: SHA256:<base64>` -- revoke key by SHA256 fingerprint
+    ///
+    /// Serials and key IDs are revoked for the `ca` key, `None` means any
+    /// CA (like `ssh-keygen -s none`). Empty lines and `#` comments are
+    /// skipped.
+    pub fn add_spec(&mut self, spec: &str, ca: Option<&PublicKey>)
+        -> Result<&mut Self, Error>
+    {
+        for line in spec.lines() {
+            // like ssh-keygen, everything after `#` is a comment, even
+            // inside of the key ID
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(value) = strip_prefix_ignore_case(line, "serial:") {
+                let value = value.trim_start();
+                let (lo, hi) = match value.find('-') {
+                    Some(idx) => {
+                        let lo = parse_serial(&value[..idx])?;
+                        let hi = parse_serial(&value[idx+1..])?;
+                        if hi <= lo {
+                            return Err(Error::InvalidFormat);
+                        }
+                        (lo, hi)
+                    }
+                    None => {
+                        let serial = parse_serial(value)?;
+                        (serial, serial)
+                    }
+                };
+                self.revoke_serial_range(ca, lo, hi)?;
+            } else if let Some(value) = strip_prefix_ignore_case(line, "id:") {
+                self.revoke_key_id(ca, value.trim_start());
+            } else if let Some(value) = strip_prefix_ignore_case(line, "hash:")
+            {
+                match Fingerprint::parse(value.trim_start())? {
+                    fp @ Fingerprint::Sha256(..) => {
+                        self.revoke_fingerprint(fp);
+                    }
+                    Fingerprint::Sha1(..) => return Err(Error::InvalidFormat),
+                }
+            } else if let Some(value) = strip_prefix_ignore_case(line, "key:") {
+                self.revoke_key(&parse_public_key(value)?);
+            } else if let Some(value) = strip_prefix_ignore_case(line, "sha1:")
+            {
+                let key = parse_public_key(value)?;
+                self.revoke_fingerprint(key.fingerprint_sha1());
+            } else if let Some(value) =
+                strip_prefix_ignore_case(line, "sha256:")
+            {
+                let key = parse_public_key(value)?;
+                self.revoke_fingerprint(key.fingerprint());
+            } else {
+                self.revoke_key(&parse_public_key(line)?);
+            }
+        }
+        Ok(self)
+    }
+    /// Build unsigned binary KRL
+    pub fn build(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(1024);
+        buf.extend(KRL_MAGIC);
+        buf.write_u32::<BigEndian>(KRL_FORMAT_VERSION).unwrap();
+        buf.write_u64::<BigEndian>(self.version).unwrap();
+        let date = self.generated_date.unwrap_or_else(|| {
+            SystemTime::now().duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs()).unwrap_or(0)
+        });
+        buf.write_u64::<BigEndian>(date).unwrap();
+        buf.write_u64::<BigEndian>(0).unwrap();  // flags
+        write_string(&mut buf, b"");  // reserved
+        write_string(&mut buf, self.comment.as_bytes());
+        for certs in &self.certificates {
+            let mut section = Vec::new();
+            write_certificates(&mut section, certs);
+            buf.push(KRL_SECTION_CERTIFICATES);
+            write_string(&mut buf, &section);
+        }
+        for &(kind, blobs) in &[(KRL_SECTION_EXPLICIT_KEY, &self.keys),
+                                (KRL_SECTION_FINGERPRINT_SHA1, &self.sha1),
+                                (KRL_SECTION_FINGERPRINT_SHA256, &self.sha256)]
+        {
+            if blobs.is_empty() {
+                continue;
+            }
+            let mut section = Vec::new();
+            for blob in blobs {
+                write_string(&mut section, blob);
+            }
+            buf.push(kind);
+            write_string(&mut buf, &section);
+        }
+        buf
+    }
+    /// Build binary KRL signed by the key
+    pub fn build_signed(&self, key: &PrivateKey) -> Result<Vec<u8>, Error> {
+        let mut buf = self.build();
+        let mut blob = Vec::with_capacity(512);
+        key.public_key().write_blob(&mut blob);
+        buf.push(KRL_SECTION_SIGNATURE);
+        write_string(&mut buf, &blob);
+        let signature = key.sign(&buf, key.default_algorithm())?;
+        write_string(&mut buf, &signature.to_bytes());
+        Ok(buf)
+    }
+    fn certificates_for(&mut self, ca: Option<&PublicKey>)
+        -> &mut RevokedCertificates
+    {
+        let idx = match self.certificates.iter()
+            .position(|c| c.ca.as_ref() == ca)
+        {
+            Some(idx) => idx,
+            None => {
+                self.certificates.push(RevokedCertificates {
+                    ca: ca.cloned(),
+                    serials: Vec::new(),
+                    key_ids: Vec::new(),
+                });
+                self.certificates.len() - 1
+            }
+        };
+        &mut self.certificates[idx]
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(line: &'a str, prefix: &str)
+    -> Option<&'a str>
+{
+    match line.get(..prefix.len()) {
+        Some(start) if start.eq_ignore_ascii_case(prefix) => {
+            Some(&line[prefix.len()..])
+        }
+        _ => None,
+    }
+}
+
+/// Parses serial like `strtoull(.., 0)` does (decimal, `0x` hex or octal)
+fn parse_serial(value: &str) -> Result<u64, Error> {
+    let result = if value.starts_with("0x") || value.starts_with("0X") {
+        u64::from_str_radix(&value[2..], 16)
+    } else if value.len() > 1 && value.starts_with('0') {
+        u64::from_str_radix(&value[1..], 8)
+    } else {
+        value.parse()
+    };
+    // from_str_radix accepts sign
+    if value.starts_with('+') {
+        return Err(Error::InvalidFormat);
+    }
+    result.map_err(|_| Error::InvalidFormat)
+}
+
+fn write_certificates(buf: &mut Vec<u8>, certs: &RevokedCertificates) {
+    match certs.ca {
+        Some(ref ca) => {
+            let mut blob = Vec::with_capacity(512);
+            ca.write_blob(&mut blob);
+            write_string(buf, &blob);
+        }
+        None => write_string(buf, b""),
+    }
+    write_string(buf, b"");  // reserved
+    write_serials(buf, &certs.serials);
+    if !certs.key_ids.is_empty() {
+        let mut section = Vec::new();
+        for key_id in &certs.key_ids {
+            write_string(&mut section, key_id.as_bytes());
+        }
+        buf.push(KRL_SECTION_CERT_KEY_ID);
+        write_string(buf, &section);
+    }
+}
+
+/// Writes serial subsections choosing the cheapest encoding like OpenSSH
+///
+/// Serial ranges must be sorted and merged.
+fn write_serials(buf: &mut Vec<u8>, serials: &[(u64, u64)]) {
+    let mut state = 0;
+    let mut section = Vec::new();
+    let mut bitmap = Vec::new();  // little endian
+    let mut bitmap_start = 0;
+    let mut last = 0;
+    for (idx, &(lo, hi)) in serials.iter().enumerate() {
+        let next = serials.get(idx + 1);
+        let contig = 1 + (hi - lo);
+        let last_gap = if state == 0 { 0 } else { lo - last };
+        let next_gap = next.map(|&(next_lo, _)| next_lo - hi).unwrap_or(0);
+        let (next_state, force_new) = choose_next_state(
+            state, contig, next.is_none(), last_gap, next_gap);
+        if state != 0 && (force_new || next_state != state ||
+                          state == KRL_SECTION_CERT_SERIAL_RANGE)
+        {
+            finish_serials(buf, state, &mut section, &mut bitmap);
+        }
+        if next_state != state || force_new {
+            state = next_state;
+            section.clear();
+            if state == KRL_SECTION_CERT_SERIAL_BITMAP {
+                bitmap_start = lo;
+                section.write_u64::<BigEndian>(lo).unwrap();
+            }
+        }
+        match state {
+            KRL_SECTION_CERT_SERIAL_LIST => {
+                for serial in lo..=hi {
+                    section.write_u64::<BigEndian>(serial).unwrap();
+                }
+            }
+            KRL_SECTION_CERT_SERIAL_RANGE => {
+                section.write_u64::<BigEndian>(lo).unwrap();
+                section.write_u64::<BigEndian>(hi).unwrap();
+            }
+            _ => {
+                for serial in lo..=hi {
+                    let bit = (serial - bitmap_start) as usize;
+                    if bitmap.len() <= bit / 8 {
+                        bitmap.resize(bit / 8 + 1, 0);
+                    }
+                    bitmap[bit / 8] |= 1 << (bit % 8);
+                }
+            }
+        }
+        last = hi;
+    }
+    if state != 0 {
+        finish_serials(buf, state, &mut section, &mut bitmap);
+    }
+}
+
+fn finish_serials(buf: &mut Vec<u8>, state: u8, section: &mut Vec<u8>,
+                  bitmap: &mut Vec<u8>)
+{
+    if state == KRL_SECTION_CERT_SERIAL_BITMAP {
+        bitmap.reverse();
+        write_mpint(section, bitmap);
+        bitmap.clear();
+    }
+    buf.push(state);
+    write_string(buf, section);
+    section.clear();
+}
+
+/// Estimates the cost (in bytes) of each encoding of the next serial range
+///
+/// This is a port of the function of the same name in OpenSSH, so that
+/// resulting KRLs are identical. Returns new state and whether a new bitmap
+/// section must be started.
+fn choose_next_state(state: u8, contig: u64, last: bool,
+                     last_gap: u64, next_gap: u64)
+    -> (u8, bool)
+{
+    let contig = min(contig, 1 << 31);
+    let last_gap = min(last_gap, 1 << 31);
+    let next_gap = min(next_gap, 1 << 31);
+    let section_start = 8 + 64;
+    let mut cost_range = 8;
+    let mut cost_bitmap_restart = section_start;
+    let (mut cost_list, mut cost_bitmap) = match state {
+        KRL_SECTION_CERT_SERIAL_LIST => (0, section_start),
+        KRL_SECTION_CERT_SERIAL_BITMAP => (8, 0),
+        _ => (8, section_start),
+    };
+    let tail = |cost| if last { 0 } else { cost };
+    cost_list += 64 * contig + tail(section_start);
+    cost_range += 2 * 64 + tail(section_start);
+    cost_bitmap += last_gap + contig + tail(min(next_gap, section_start));
+    cost_bitmap_restart += contig + tail(min(next_gap, section_start));
+
+    let bytes = |bits: u64| bits.div_ceil(8);
+    let mut result = (KRL_SECTION_CERT_SERIAL_BITMAP, false);
+    let mut cost = bytes(cost_bitmap);
+    if bytes(cost_range) < cost {
+        result = (KRL_SECTION_CERT_SERIAL_RANGE, false);
+        cost = bytes(cost_range);
+    }
+    if bytes(cost_list) < cost {
+        result = (KRL_SECTION_CERT_SERIAL_LIST, false);
+        cost = bytes(cost_list);
+    }
+    if bytes(cost_bitmap_restart) < cost {
+        result = (KRL_SECTION_CERT_SERIAL_BITMAP, true);
+    }
+    return result;
+}
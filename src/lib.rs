@@ -2,24 +2,69 @@
 //!
 //! The only key format supported so far is ``openssh``.
 //!
+//! Enable ``serde`` feature to (de)serialize ``PublicKey`` and
+//! ``SerdePrivateKey``.
+//!
+//! The crate works without the standard library (but requires ``alloc``),
+//! if default ``std`` feature is disabled. Modules which do I/O (``agent``,
+//! ``krl``, ``weak`` and ``files``) and ``openssh::PublicKeyReader`` are
+//! only available with ``std``. Signing with ECDSA P-521 keys also requires
+//! ``std``, as it uses random nonce from the operating system.
+//!
 //! [Docs](https://docs.rs/ssh-keys/) |
 //! [Github](https://github.com/tailhook/ssh-keys/) |
 //! [Crate](https://crates.io/crates/ssh-keys)
 //!
+#![no_std]
 #![warn(missing_docs)]
 #![warn(missing_debug_implementations)]
 
-extern crate base64;
+#[cfg(feature="std")] extern crate std;
+#[macro_use] extern crate alloc;
+extern crate base64ct;
 extern crate byteorder;
-#[macro_use] extern crate quick_error;
+#[cfg(feature="std")] extern crate md5;
+extern crate ed25519_dalek;
+extern crate p256;
+extern crate p384;
+extern crate p521;
+extern crate rsa;
+extern crate sha1;
+extern crate sha2;
+extern crate subtle;
+extern crate zeroize;
+#[cfg(feature="serde")] extern crate serde;
+#[cfg(feature="std")] extern crate hmac;
+#[cfg(feature="std")] extern crate rand_core;
+#[cfg(all(feature="std", unix))] extern crate libc;
 
 mod error;
 mod debug;
 mod stdimpls;
 mod conversion;
+mod signature;
+mod fingerprint;
+mod validate;
+mod matching;
+#[cfg(feature="std")] mod key_reader;
+#[cfg(feature="serde")] mod serde_impls;
 pub mod openssh;
+pub mod allowed_signers;
+#[cfg(feature="std")] pub mod agent;
+pub mod sshsig;
+#[cfg(feature="std")] pub mod krl;
+pub mod wire;
+pub mod policy;
+#[cfg(feature="std")] pub mod weak;
+#[cfg(feature="std")] pub mod files;
+
+use alloc::vec::Vec;
 
 pub use error::Error;
+pub use signature::{Signature, SignatureAlgorithm};
+pub use fingerprint::Fingerprint;
+pub use matching::{KeyMatch, pair_keys};
+#[cfg(feature="serde")] pub use serde_impls::SerdePrivateKey;
 
 /// Public key enum
 pub enum PublicKey {
@@ -28,9 +73,35 @@ pub enum PublicKey {
     Rsa { exponent: Vec<u8>, modulus: Vec<u8> },
     /// Ed25519 (eliptic curves) key
     Ed25519([u8; 32]),
+    /// ECDSA key on one of the NIST curves
+    ///
+    /// The `point` is an uncompressed SEC1-encoded curve point
+    #[allow(missing_docs)]
+    Ecdsa { curve: EcdsaCurve, point: Vec<u8> },
+}
+
+/// Public key borrowing its data (e.g. from a decoded key blob)
+///
+/// Can be parsed and compared to [`PublicKey`](enum.PublicKey.html) without
+/// allocating memory.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PublicKeyRef<'a> {
+    /// RSA key
+    #[allow(missing_docs)]
+    Rsa { exponent: &'a [u8], modulus: &'a [u8] },
+    /// Ed25519 (eliptic curves) key
+    Ed25519(&'a [u8; 32]),
+    /// ECDSA key on one of the NIST curves
+    ///
+    /// The `point` is an uncompressed SEC1-encoded curve point
+    #[allow(missing_docs)]
+    Ecdsa { curve: EcdsaCurve, point: &'a [u8] },
 }
 
 /// Secret key enum
+///
+/// Key material is zeroized on drop, so fields can't be moved out of the
+/// key, match on a reference and clone them if needed.
 pub enum PrivateKey {
     /// RSA key
     #[allow(missing_docs)]
@@ -38,4 +109,21 @@ pub enum PrivateKey {
           p: Vec<u8>, q: Vec<u8> },
     /// Ed25519 (eliptic curves) key
     Ed25519([u8; 64]),
+    /// ECDSA key on one of the NIST curves
+    ///
+    /// The `scalar` is the private key (as stored in the key file, i.e. it
+    /// may contain a leading zero byte)
+    #[allow(missing_docs)]
+    Ecdsa { curve: EcdsaCurve, point: Vec<u8>, scalar: Vec<u8> },
+}
+
+/// Elliptic curve used by ECDSA key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EcdsaCurve {
+    /// NIST P-256 (`nistp256`)
+    NistP256,
+    /// NIST P-384 (`nistp384`)
+    NistP384,
+    /// NIST P-521 (`nistp521`)
+    NistP521,
 }
@@ -0,0 +1,83 @@
+use alloc::vec::Vec;
+
+use {PrivateKey, PublicKey, PublicKeyRef, Fingerprint};
+
+
+/// Public key or its fingerprint which private keys can be matched against
+///
+/// See [`pair_keys`](fn.pair_keys.html).
+pub trait KeyMatch {
+    /// Returns true if this value identifies the public key
+    fn matches_key(&self, key: PublicKeyRef) -> bool;
+    /// Returns the fingerprint if the value is a fingerprint
+    ///
+    /// This lets [`pair_keys`](fn.pair_keys.html) hash each private key
+    /// only once instead of doing that for every pair.
+    fn as_fingerprint(&self) -> Option<&Fingerprint> {
+        None
+    }
+}
+
+impl KeyMatch for PublicKey {
+    fn matches_key(&self, key: PublicKeyRef) -> bool {
+        self.as_key_ref() == key
+    }
+}
+
+impl<'a> KeyMatch for PublicKeyRef<'a> {
+    fn matches_key(&self, key: PublicKeyRef) -> bool {
+        *self == key
+    }
+}
+
+impl KeyMatch for Fingerprint {
+    fn matches_key(&self, key: PublicKeyRef) -> bool {
+        match *self {
+            Fingerprint::Sha256(..) => key.fingerprint() == *self,
+            Fingerprint::Sha1(..) => key.fingerprint_sha1() == *self,
+        }
+    }
+    fn as_fingerprint(&self) -> Option<&Fingerprint> {
+        Some(self)
+    }
+}
+
+/// Find private keys corresponding to public keys (or fingerprints)
+///
+/// Returns `(private_index, public_index)` pairs in the order of private
+/// keys. Keys which aren't listed in any pair have no counterpart.
+///
+/// ```rust,ignore
+/// let pairs = pair_keys(&private_keys, &fingerprints);
+/// for (idx, fingerprint) in fingerprints.iter().enumerate() {
+///     if !pairs.iter().any(|&(_, public)| public == idx) {
+///         println!("No private key for {}", fingerprint);
+///     }
+/// }
+/// ```
+pub fn pair_keys<T: KeyMatch>(private: &[PrivateKey], public: &[T])
+    -> Vec<(usize, usize)>
+{
+    let mut pairs = Vec::new();
+    for (pidx, key) in private.iter().enumerate() {
+        let key = key.public_key_ref();
+        // computed on first use
+        let mut sha256 = None;
+        let mut sha1 = None;
+        for (idx, item) in public.iter().enumerate() {
+            let matches = match item.as_fingerprint() {
+                Some(fp @ &Fingerprint::Sha256(..)) => {
+                    *sha256.get_or_insert_with(|| key.fingerprint()) == *fp
+                }
+                Some(fp @ &Fingerprint::Sha1(..)) => {
+                    *sha1.get_or_insert_with(|| key.fingerprint_sha1()) == *fp
+                }
+                None => item.matches_key(key),
+            };
+            if matches {
+                pairs.push((pidx, idx));
+            }
+        }
+    }
+    return pairs;
+}
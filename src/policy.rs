@@ -0,0 +1,184 @@
+//! Key strength policy
+//!
+//! [`KeyPolicy`](struct.KeyPolicy.html) describes which keys are acceptable
+//! and reports every violation of the key, so that it can be used for
+//! auditing `authorized_keys` files:
+//!
+//! ```rust,ignore
+//! let mut policy = KeyPolicy::new();
+//! policy.min_rsa_bits(3072).forbid_dsa(true);
+//! for violation in policy.check(&key) {
+//!     println!("{}: {}", key, violation);
+//! }
+//! ```
+//!
+//! Keys of types that this library doesn't support (e.g. `ssh-dss` or
+//! security keys) can't be parsed, but their type name is returned in
+//! [`Error::UnsupportedType`](../enum.Error.html), and can be checked with
+//! [`check_type`](struct.KeyPolicy.html#method.check_type).
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use {PublicKey, EcdsaCurve, Signature, SignatureAlgorithm};
+
+
+/// A set of requirements for public keys
+///
+/// Policy created by [`new`](#method.new) allows everything.
+#[derive(Debug, Clone, Default)]
+pub struct KeyPolicy {
+    allowed_types: Option<Vec<String>>,
+    min_rsa_bits: usize,
+    min_ecdsa_curve: Option<EcdsaCurve>,
+    forbid_dsa: bool,
+    forbid_sha1: bool,
+    require_security_key: bool,
+}
+
+/// A single reason why key doesn't satisfy the policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// Key type is not in the list of allowed types
+    TypeNotAllowed(String),
+    /// RSA modulus is shorter than required
+    #[allow(missing_docs)]
+    RsaTooShort { bits: usize, min_bits: usize },
+    /// ECDSA key uses a curve weaker than required
+    #[allow(missing_docs)]
+    CurveTooWeak { curve: EcdsaCurve, min_curve: EcdsaCurve },
+    /// Key is a DSA key
+    Dsa,
+    /// Signature is made with SHA-1 (`ssh-rsa` algorithm)
+    Sha1Signature,
+    /// Key is not backed by a hardware security key (FIDO)
+    NotSecurityKey,
+}
+
+impl KeyPolicy {
+    /// Create a policy which allows any key
+    pub fn new() -> KeyPolicy {
+        KeyPolicy::default()
+    }
+    /// Allow only keys of these types (e.g. `ssh-ed25519`)
+    ///
+    /// All types are allowed by default.
+    pub fn allowed_types<I, S>(&mut self, types: I) -> &mut Self
+        where I: IntoIterator<Item=S>,
+              S: Into<String>,
+    {
+        self.allowed_types = Some(types.into_iter().map(Into::into).collect());
+        self
+    }
+    /// Set minimum size of RSA modulus in bits
+    pub fn min_rsa_bits(&mut self, bits: usize) -> &mut Self {
+        self.min_rsa_bits = bits;
+        self
+    }
+    /// Set the weakest ECDSA curve that is allowed
+    pub fn min_ecdsa_curve(&mut self, curve: EcdsaCurve) -> &mut Self {
+        self.min_ecdsa_curve = Some(curve);
+        self
+    }
+    /// Forbid `ssh-dss` keys
+    pub fn forbid_dsa(&mut self, value: bool) -> &mut Self {
+        self.forbid_dsa = value;
+        self
+    }
+    /// Forbid `ssh-rsa` (SHA-1) signatures
+    ///
+    /// Only applies to [`check_signature`](#method.check_signature), RSA
+    /// keys themselves can be used with SHA-2 signatures.
+    pub fn forbid_sha1_signatures(&mut self, value: bool) -> &mut Self {
+        self.forbid_sha1 = value;
+        self
+    }
+    /// Require keys to be backed by a security key (`sk-*` key types)
+    pub fn require_security_key(&mut self, value: bool) -> &mut Self {
+        self.require_security_key = value;
+        self
+    }
+    /// Check the key against the policy
+    ///
+    /// Returns all violations found, empty vector means key is allowed.
+    pub fn check(&self, key: &PublicKey) -> Vec<Violation> {
+        let mut result = self.check_type(key.key_type());
+        match *key {
+            PublicKey::Rsa { .. } => {
+                let bits = key.bits();
+                if bits < self.min_rsa_bits {
+                    result.push(Violation::RsaTooShort {
+                        bits: bits,
+                        min_bits: self.min_rsa_bits,
+                    });
+                }
+            }
+            PublicKey::Ecdsa { curve, .. } => {
+                if let Some(min_curve) = self.min_ecdsa_curve {
+                    if curve.bits() < min_curve.bits() {
+                        result.push(Violation::CurveTooWeak {
+                            curve: curve,
+                            min_curve: min_curve,
+                        });
+                    }
+                }
+            }
+            PublicKey::Ed25519(..) => {}
+        }
+        return result;
+    }
+    /// Check key type name (e.g. `ssh-dss`) against the policy
+    ///
+    /// This is useful for keys which can't be parsed by this library, size
+    /// of the key is not checked.
+    pub fn check_type(&self, key_type: &str) -> Vec<Violation> {
+        let mut result = Vec::new();
+        if let Some(ref allowed) = self.allowed_types {
+            if !allowed.iter().any(|x| x == key_type) {
+                result.push(Violation::TypeNotAllowed(key_type.to_string()));
+            }
+        }
+        if self.forbid_dsa && key_type == "ssh-dss" {
+            result.push(Violation::Dsa);
+        }
+        if self.require_security_key && !key_type.starts_with("sk-") {
+            result.push(Violation::NotSecurityKey);
+        }
+        return result;
+    }
+    /// Check signature algorithm against the policy
+    pub fn check_signature(&self, signature: &Signature) -> Vec<Violation> {
+        let mut result = Vec::new();
+        if self.forbid_sha1 &&
+            signature.algorithm() == SignatureAlgorithm::RsaSha1
+        {
+            result.push(Violation::Sha1Signature);
+        }
+        return result;
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::TypeNotAllowed(ref typ) => {
+                write!(f, "key type {:?} is not allowed", typ)
+            }
+            Violation::RsaTooShort { bits, min_bits } => {
+                write!(f, "RSA key has {} bits, at least {} required",
+                       bits, min_bits)
+            }
+            Violation::CurveTooWeak { curve, min_curve } => {
+                write!(f, "curve {} is weaker than {}",
+                       curve.name(), min_curve.name())
+            }
+            Violation::Dsa => write!(f, "DSA keys are forbidden"),
+            Violation::Sha1Signature => {
+                write!(f, "SHA-1 signatures are forbidden")
+            }
+            Violation::NotSecurityKey => {
+                write!(f, "key is not backed by a security key")
+            }
+        }
+    }
+}
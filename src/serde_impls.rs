@@ -0,0 +1,116 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use openssh::{parse_public_blob, parse_public_key, parse_private_key};
+use openssh::{read_private_key, format_private_key};
+use wire::Reader;
+use {PublicKey, PrivateKey};
+
+
+/// Wrapper which enables serialization of a private key
+///
+/// `PrivateKey` itself doesn't implement `Serialize`, so that secrets aren't
+/// written into a config or a log by accident.
+///
+/// In human-readable formats key is serialized as unencrypted
+/// `openssh-key-v1` file contents (both that and the old PEM RSA format are
+/// accepted on deserialization), in binary formats in the ssh-agent wire
+/// format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerdePrivateKey(pub PrivateKey);
+
+impl Serialize for PublicKey {
+    fn serialize<S: Serializer>(&self, serializer: S)
+        -> Result<S::Ok, S::Error>
+    {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            let mut blob = Vec::with_capacity(512);
+            self.write_blob(&mut blob);
+            serializer.serialize_bytes(&blob)
+        }
+    }
+}
+
+struct PublicKeyVisitor;
+
+impl<'de> Visitor<'de> for PublicKeyVisitor {
+    type Value = PublicKey;
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an openssh public key")
+    }
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<PublicKey, E> {
+        parse_public_key(value).map_err(E::custom)
+    }
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<PublicKey, E> {
+        parse_public_blob(value).map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D)
+        -> Result<PublicKey, D::Error>
+    {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(PublicKeyVisitor)
+        } else {
+            deserializer.deserialize_bytes(PublicKeyVisitor)
+        }
+    }
+}
+
+impl Serialize for SerdePrivateKey {
+    fn serialize<S: Serializer>(&self, serializer: S)
+        -> Result<S::Ok, S::Error>
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&format_private_key(&self.0))
+        } else {
+            serializer.serialize_bytes(&self.0.to_blob())
+        }
+    }
+}
+
+struct PrivateKeyVisitor;
+
+impl<'de> Visitor<'de> for PrivateKeyVisitor {
+    type Value = SerdePrivateKey;
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an openssh private key")
+    }
+    fn visit_str<E: de::Error>(self, value: &str)
+        -> Result<SerdePrivateKey, E>
+    {
+        let mut keys = parse_private_key(value).map_err(E::custom)?;
+        if keys.len() != 1 {
+            return Err(E::invalid_length(keys.len(), &"exactly one key"));
+        }
+        Ok(SerdePrivateKey(keys.remove(0)))
+    }
+    fn visit_bytes<E: de::Error>(self, value: &[u8])
+        -> Result<SerdePrivateKey, E>
+    {
+        let mut cur = Reader::new(value);
+        let key = read_private_key(&mut cur).map_err(E::custom)?;
+        if !cur.is_empty() {
+            return Err(E::invalid_length(value.len(), &self));
+        }
+        Ok(SerdePrivateKey(key))
+    }
+}
+
+impl<'de> Deserialize<'de> for SerdePrivateKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D)
+        -> Result<SerdePrivateKey, D::Error>
+    {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(PrivateKeyVisitor)
+        } else {
+            deserializer.deserialize_bytes(PrivateKeyVisitor)
+        }
+    }
+}
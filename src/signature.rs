@@ -0,0 +1,360 @@
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use ed25519_dalek;
+use p256;
+use p384;
+use p521;
+use rsa::{BigUint, Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512, Digest};
+use zeroize::Zeroizing;
+
+use wire::{Reader, write_mpint};
+use {PrivateKey, PublicKey, EcdsaCurve, Error};
+
+/// Largest RSA key (in bits) we are going to verify signatures with
+const RSA_MAX_BITS: usize = 16384;
+
+
+/// Signature algorithm
+///
+/// For RSA keys the algorithm also determines the hash function, for other
+/// key types there is exactly one algorithm for each kind of key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignatureAlgorithm {
+    /// `ssh-ed25519`
+    Ed25519,
+    /// `ssh-rsa`, i.e. RSA with SHA-1 hash (RFC 4253), deprecated
+    RsaSha1,
+    /// `rsa-sha2-256` (RFC 8332)
+    RsaSha256,
+    /// `rsa-sha2-512` (RFC 8332)
+    RsaSha512,
+    /// `ecdsa-sha2-nistp256` (RFC 5656)
+    EcdsaNistP256,
+    /// `ecdsa-sha2-nistp384` (RFC 5656)
+    EcdsaNistP384,
+    /// `ecdsa-sha2-nistp521` (RFC 5656)
+    EcdsaNistP521,
+}
+
+/// Signature in SSH format
+///
+/// Consists of algorithm and the algorithm-specific signature blob.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    algorithm: SignatureAlgorithm,
+    blob: Vec<u8>,
+}
+
+impl SignatureAlgorithm {
+    /// Algorithm name as used in SSH protocol
+    pub fn name(&self) -> &'static str {
+        use SignatureAlgorithm::*;
+        match *self {
+            Ed25519 => "ssh-ed25519",
+            RsaSha1 => "ssh-rsa",
+            RsaSha256 => "rsa-sha2-256",
+            RsaSha512 => "rsa-sha2-512",
+            EcdsaNistP256 => "ecdsa-sha2-nistp256",
+            EcdsaNistP384 => "ecdsa-sha2-nistp384",
+            EcdsaNistP521 => "ecdsa-sha2-nistp521",
+        }
+    }
+    /// Find algorithm by its name in SSH protocol
+    pub fn from_name(name: &str) -> Option<SignatureAlgorithm> {
+        use SignatureAlgorithm::*;
+        match name {
+            "ssh-ed25519" => Some(Ed25519),
+            "ssh-rsa" => Some(RsaSha1),
+            "rsa-sha2-256" => Some(RsaSha256),
+            "rsa-sha2-512" => Some(RsaSha512),
+            "ecdsa-sha2-nistp256" => Some(EcdsaNistP256),
+            "ecdsa-sha2-nistp384" => Some(EcdsaNistP384),
+            "ecdsa-sha2-nistp521" => Some(EcdsaNistP521),
+            _ => None,
+        }
+    }
+    /// ECDSA algorithm for the specified curve
+    pub fn for_curve(curve: EcdsaCurve) -> SignatureAlgorithm {
+        match curve {
+            EcdsaCurve::NistP256 => SignatureAlgorithm::EcdsaNistP256,
+            EcdsaCurve::NistP384 => SignatureAlgorithm::EcdsaNistP384,
+            EcdsaCurve::NistP521 => SignatureAlgorithm::EcdsaNistP521,
+        }
+    }
+}
+
+impl Signature {
+    /// Create signature from algorithm and a raw signature blob
+    pub fn new(algorithm: SignatureAlgorithm, blob: Vec<u8>) -> Signature {
+        Signature { algorithm: algorithm, blob: blob }
+    }
+    /// Signature algorithm
+    pub fn algorithm(&self) -> SignatureAlgorithm {
+        self.algorithm
+    }
+    /// Algorithm-specific signature blob
+    ///
+    /// This is 64 bytes of signature for ed25519, signature integer padded
+    /// to the modulus length for RSA, and `mpint r` followed by `mpint s` for
+    /// ECDSA.
+    pub fn blob(&self) -> &[u8] {
+        &self.blob
+    }
+    /// Serialize signature in SSH wire format
+    ///
+    /// This is `string algorithm` followed by `string blob`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let name = self.algorithm.name();
+        let mut buf = Vec::with_capacity(name.len() + self.blob.len() + 8);
+        buf.extend_from_slice(&(name.len() as u32).to_be_bytes());
+        buf.extend(name.as_bytes());
+        buf.extend_from_slice(&(self.blob.len() as u32).to_be_bytes());
+        buf.extend(&self.blob);
+        return buf;
+    }
+    /// Parse signature from SSH wire format
+    pub fn from_bytes(data: &[u8]) -> Result<Signature, Error> {
+        let mut cur = Reader::new(data);
+        let name = cur.read_string()?;
+        let alg = SignatureAlgorithm::from_name(name)
+            .ok_or_else(|| Error::UnsupportedType(name.to_string()))?;
+        let blob = cur.read_bytes()?;
+        if !cur.is_empty() {
+            return Err(Error::InvalidFormat);
+        }
+        Ok(Signature::new(alg, blob.to_vec()))
+    }
+}
+
+impl PrivateKey {
+    /// Preferred signature algorithm for the key (`rsa-sha2-512` for RSA)
+    pub(crate) fn default_algorithm(&self) -> SignatureAlgorithm {
+        match *self {
+            PrivateKey::Ed25519(..) => SignatureAlgorithm::Ed25519,
+            PrivateKey::Rsa { .. } => SignatureAlgorithm::RsaSha512,
+            PrivateKey::Ecdsa { curve, .. }
+            => SignatureAlgorithm::for_curve(curve),
+        }
+    }
+    /// Sign data with this key
+    ///
+    /// For RSA keys any of the `ssh-rsa`, `rsa-sha2-256`, `rsa-sha2-512`
+    /// algorithms can be used, for other keys algorithm must match the type
+    /// of the key.
+    pub fn sign(&self, data: &[u8], alg: SignatureAlgorithm)
+        -> Result<Signature, Error>
+    {
+        use SignatureAlgorithm::*;
+        let blob = match (self, alg) {
+            (PrivateKey::Ed25519(key), Ed25519) => {
+                let mut seed = Zeroizing::new([0u8; 32]);
+                seed.copy_from_slice(&key[..32]);
+                let key = ed25519_dalek::SigningKey::from_bytes(&seed);
+                use ed25519_dalek::Signer;
+                key.sign(data).to_bytes().to_vec()
+            }
+            (PrivateKey::Rsa { n, e, d, p, q, .. },
+             RsaSha1) |
+            (PrivateKey::Rsa { n, e, d, p, q, .. },
+             RsaSha256) |
+            (PrivateKey::Rsa { n, e, d, p, q, .. },
+             RsaSha512) => {
+                let key = RsaPrivateKey::from_components(
+                    BigUint::from_bytes_be(n),
+                    BigUint::from_bytes_be(e),
+                    BigUint::from_bytes_be(d),
+                    vec![BigUint::from_bytes_be(p),
+                         BigUint::from_bytes_be(q)],
+                ).map_err(|_| Error::InvalidKey)?;
+                let res = match alg {
+                    RsaSha1 => key.sign(Pkcs1v15Sign::new::<Sha1>(),
+                                        &Sha1::digest(data)),
+                    RsaSha256 => key.sign(Pkcs1v15Sign::new::<Sha256>(),
+                                          &Sha256::digest(data)),
+                    _ => key.sign(Pkcs1v15Sign::new::<Sha512>(),
+                                  &Sha512::digest(data)),
+                };
+                res.map_err(|_| Error::InvalidKey)?
+            }
+            (PrivateKey::Ecdsa { curve, scalar, .. }, _)
+            if SignatureAlgorithm::for_curve(*curve) == alg
+            => {
+                ecdsa_sign(*curve, scalar, data)?
+            }
+            _ => return Err(Error::IncompatibleAlgorithm(alg.name())),
+        };
+        Ok(Signature::new(alg, blob))
+    }
+}
+
+impl PublicKey {
+    /// Verify signature of the data
+    ///
+    /// Returns `Ok(())` if signature is valid. All algorithms applicable to
+    /// the key are accepted, including `ssh-rsa` which uses SHA-1, use
+    /// [`verify_strict`](#method.verify_strict) to reject them.
+    pub fn verify(&self, data: &[u8], signature: &Signature)
+        -> Result<(), Error>
+    {
+        use SignatureAlgorithm::*;
+        let alg = signature.algorithm();
+        let blob = signature.blob();
+        match (self, alg) {
+            (PublicKey::Ed25519(key), Ed25519) => {
+                use ed25519_dalek::Verifier;
+                let key = ed25519_dalek::VerifyingKey::from_bytes(key)
+                    .map_err(|_| Error::InvalidKey)?;
+                let sig = ed25519_dalek::Signature::from_slice(blob)
+                    .map_err(|_| Error::InvalidSignature)?;
+                key.verify(data, &sig).map_err(|_| Error::InvalidSignature)
+            }
+            (PublicKey::Rsa { exponent, modulus }, RsaSha1) |
+            (PublicKey::Rsa { exponent, modulus }, RsaSha256) |
+            (PublicKey::Rsa { exponent, modulus }, RsaSha512) => {
+                let key = RsaPublicKey::new_with_max_size(
+                    BigUint::from_bytes_be(modulus),
+                    BigUint::from_bytes_be(exponent),
+                    RSA_MAX_BITS,
+                ).map_err(|_| Error::InvalidKey)?;
+                // signature may be shorter than modulus, like openssh
+                // we allow that
+                use rsa::traits::PublicKeyParts;
+                let size = key.size();
+                if blob.len() > size {
+                    return Err(Error::InvalidSignature);
+                }
+                let mut sig = vec![0u8; size];
+                sig[size - blob.len()..].copy_from_slice(blob);
+                let res = match alg {
+                    RsaSha1 => key.verify(Pkcs1v15Sign::new::<Sha1>(),
+                                          &Sha1::digest(data), &sig),
+                    RsaSha256 => key.verify(Pkcs1v15Sign::new::<Sha256>(),
+                                            &Sha256::digest(data), &sig),
+                    _ => key.verify(Pkcs1v15Sign::new::<Sha512>(),
+                                    &Sha512::digest(data), &sig),
+                };
+                res.map_err(|_| Error::InvalidSignature)
+            }
+            (PublicKey::Ecdsa { curve, point }, _)
+            if SignatureAlgorithm::for_curve(*curve) == alg
+            => {
+                ecdsa_verify(*curve, point, data, blob)
+            }
+            _ => Err(Error::IncompatibleAlgorithm(alg.name())),
+        }
+    }
+    /// Verify signature of the data, rejecting SHA-1 based signatures
+    ///
+    /// This is similar to what recent versions of OpenSSH do by default:
+    /// `ssh-rsa` signatures are rejected with `Error::InsecureAlgorithm`.
+    pub fn verify_strict(&self, data: &[u8], signature: &Signature)
+        -> Result<(), Error>
+    {
+        if signature.algorithm() == SignatureAlgorithm::RsaSha1 {
+            return Err(Error::InsecureAlgorithm(
+                signature.algorithm().name()));
+        }
+        self.verify(data, signature)
+    }
+}
+
+/// Converts scalar as stored in key file into fixed-size field bytes
+pub(crate) fn field_bytes(curve: EcdsaCurve, scalar: &[u8])
+    -> Result<Zeroizing<Vec<u8>>, Error>
+{
+    let size = curve.field_size();
+    let start = scalar.iter().position(|&x| x != 0).unwrap_or(scalar.len());
+    let value = &scalar[start..];
+    if value.len() > size {
+        return Err(Error::InvalidKey);
+    }
+    let mut result = Zeroizing::new(vec![0u8; size]);
+    result[size - value.len()..].copy_from_slice(value);
+    return Ok(result);
+}
+
+fn ecdsa_sign(curve: EcdsaCurve, scalar: &[u8], data: &[u8])
+    -> Result<Vec<u8>, Error>
+{
+    let scalar = field_bytes(curve, scalar)?;
+    let (r, s) = match curve {
+        EcdsaCurve::NistP256 => {
+            use p256::ecdsa::signature::Signer;
+            let key = p256::ecdsa::SigningKey::from_slice(&scalar)
+                .map_err(|_| Error::InvalidKey)?;
+            let sig: p256::ecdsa::Signature = key.sign(data);
+            (sig.r().to_bytes().to_vec(), sig.s().to_bytes().to_vec())
+        }
+        EcdsaCurve::NistP384 => {
+            use p384::ecdsa::signature::Signer;
+            let key = p384::ecdsa::SigningKey::from_slice(&scalar)
+                .map_err(|_| Error::InvalidKey)?;
+            let sig: p384::ecdsa::Signature = key.sign(data);
+            (sig.r().to_bytes().to_vec(), sig.s().to_bytes().to_vec())
+        }
+        // p521 signs with random nonce, so needs OS random generator
+        #[cfg(feature="std")]
+        EcdsaCurve::NistP521 => {
+            use p521::ecdsa::signature::Signer;
+            let key = p521::ecdsa::SigningKey::from_slice(&scalar)
+                .map_err(|_| Error::InvalidKey)?;
+            let sig: p521::ecdsa::Signature = key.sign(data);
+            (sig.r().to_bytes().to_vec(), sig.s().to_bytes().to_vec())
+        }
+        #[cfg(not(feature="std"))]
+        EcdsaCurve::NistP521 => {
+            return Err(Error::UnsupportedType("ecdsa-sha2-nistp521".into()));
+        }
+    };
+    let mut buf = Vec::with_capacity(2*curve.field_size() + 10);
+    write_mpint(&mut buf, &r);
+    write_mpint(&mut buf, &s);
+    return Ok(buf);
+}
+
+fn ecdsa_verify(curve: EcdsaCurve, point: &[u8], data: &[u8], blob: &[u8])
+    -> Result<(), Error>
+{
+    // ssh uses pair of mpints, while ecdsa implementations expect either
+    // fixed size scalars or DER, so convert r and s into fixed size scalars
+    let mut cur = Reader::new(blob);
+    let r = cur.read_mpint_strict()
+        .map_err(|_| Error::InvalidSignature)?;
+    let s = cur.read_mpint_strict()
+        .map_err(|_| Error::InvalidSignature)?;
+    if !cur.is_empty() {
+        return Err(Error::InvalidSignature);
+    }
+    let mut rs = field_bytes(curve, r).map_err(|_| Error::InvalidSignature)?;
+    let s = field_bytes(curve, s).map_err(|_| Error::InvalidSignature)?;
+    rs.extend(s.iter());
+    match curve {
+        EcdsaCurve::NistP256 => {
+            use p256::ecdsa::signature::Verifier;
+            let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(point)
+                .map_err(|_| Error::InvalidKey)?;
+            let sig = p256::ecdsa::Signature::from_slice(&rs)
+                .map_err(|_| Error::InvalidSignature)?;
+            key.verify(data, &sig).map_err(|_| Error::InvalidSignature)
+        }
+        EcdsaCurve::NistP384 => {
+            use p384::ecdsa::signature::Verifier;
+            let key = p384::ecdsa::VerifyingKey::from_sec1_bytes(point)
+                .map_err(|_| Error::InvalidKey)?;
+            let sig = p384::ecdsa::Signature::from_slice(&rs)
+                .map_err(|_| Error::InvalidSignature)?;
+            key.verify(data, &sig).map_err(|_| Error::InvalidSignature)
+        }
+        EcdsaCurve::NistP521 => {
+            use p521::ecdsa::signature::Verifier;
+            let key = p521::ecdsa::VerifyingKey::from_sec1_bytes(point)
+                .map_err(|_| Error::InvalidKey)?;
+            let sig = p521::ecdsa::Signature::from_slice(&rs)
+                .map_err(|_| Error::InvalidSignature)?;
+            key.verify(data, &sig).map_err(|_| Error::InvalidSignature)
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Detached file signatures compatible with `ssh-keygen -Y sign`
+//!
+//! This implements the SSHSIG format as described in `PROTOCOL.sshsig` of
+//! the OpenSSH distribution. Such signatures are used for signing git
+//! commits and arbitrary files.
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use base64ct::{Base64, Encoding};
+use sha2::{Sha256, Sha512, Digest};
+
+use openssh::{b64decode, parse_public_blob};
+use wire::{Reader, write_string};
+use {PublicKey, PrivateKey, Signature, Error};
+
+
+const MAGIC_PREAMBLE: &[u8] = b"SSHSIG";
+const SIG_VERSION: u32 = 1;
+const BEGIN: &str = "-----BEGIN SSH SIGNATURE-----";
+const END: &str = "-----END SSH SIGNATURE-----";
+/// The width of base64 lines used by openssh
+const LINE_WIDTH: usize = 70;
+
+/// Hash algorithm used to digest the message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    /// `sha256`
+    Sha256,
+    /// `sha512` (default in `ssh-keygen`)
+    Sha512,
+}
+
+/// A parsed (or created) SSHSIG signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshSig {
+    public_key: PublicKey,
+    namespace: String,
+    hash_algorithm: HashAlgorithm,
+    signature: Signature,
+}
+
+impl HashAlgorithm {
+    /// Name of the algorithm as used in SSHSIG
+    pub fn name(&self) -> &'static str {
+        match *self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha512 => "sha512",
+        }
+    }
+    /// Find algorithm by name
+    pub fn from_name(name: &str) -> Option<HashAlgorithm> {
+        match name {
+            "sha256" => Some(HashAlgorithm::Sha256),
+            "sha512" => Some(HashAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+    fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match *self {
+            HashAlgorithm::Sha256 => Sha256::digest(data).to_vec(),
+            HashAlgorithm::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+}
+
+/// Returns data that is actually signed by the key
+fn signed_data(namespace: &str, hash_algorithm: HashAlgorithm, data: &[u8])
+    -> Vec<u8>
+{
+    let mut buf = Vec::with_capacity(128);
+    buf.extend(MAGIC_PREAMBLE);
+    write_string(&mut buf, namespace.as_bytes());
+    write_string(&mut buf, b"");  // reserved
+    write_string(&mut buf, hash_algorithm.name().as_bytes());
+    write_string(&mut buf, &hash_algorithm.digest(data));
+    return buf;
+}
+
+impl SshSig {
+    /// Sign data with the private key
+    ///
+    /// Namespace is an arbitrary string that prevents signatures from being
+    /// reused in a different context, `ssh-keygen` uses `file` by default
+    /// and git uses `git`. RSA keys are signed with `rsa-sha2-512` like
+    /// `ssh-keygen` does.
+    pub fn sign(key: &PrivateKey, namespace: &str,
+                hash_algorithm: HashAlgorithm, data: &[u8])
+        -> Result<SshSig, Error>
+    {
+        let signature = key.sign(
+            &signed_data(namespace, hash_algorithm, data),
+            key.default_algorithm())?;
+        Ok(SshSig {
+            public_key: key.public_key(),
+            namespace: namespace.to_string(),
+            hash_algorithm: hash_algorithm,
+            signature: signature,
+        })
+    }
+    /// Verify signature of the data
+    ///
+    /// Checks that signature was made by the `key`, for the specified
+    /// `namespace` and that data matches. Like in OpenSSH, RSA signatures
+    /// using SHA-1 are rejected.
+    pub fn verify(&self, key: &PublicKey, namespace: &str, data: &[u8])
+        -> Result<(), Error>
+    {
+        if &self.public_key != key {
+            return Err(Error::KeyMismatch);
+        }
+        if self.namespace != namespace {
+            return Err(Error::NamespaceMismatch);
+        }
+        key.verify_strict(
+            &signed_data(namespace, self.hash_algorithm, data),
+            &self.signature)
+    }
+    /// Public key embedded in the signature
+    ///
+    /// Note: this key is not trusted by itself, it's normally checked
+    /// against the list of allowed signers.
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+    /// Namespace of the signature
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+    /// Hash algorithm used to digest message
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash_algorithm
+    }
+    /// The signature itself
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+    /// Serialize signature into binary SSHSIG format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(512);
+        buf.extend(MAGIC_PREAMBLE);
+        buf.extend_from_slice(&SIG_VERSION.to_be_bytes());
+        let mut key = Vec::with_capacity(512);
+        self.public_key.write_blob(&mut key);
+        write_string(&mut buf, &key);
+        write_string(&mut buf, self.namespace.as_bytes());
+        write_string(&mut buf, b"");  // reserved
+        write_string(&mut buf, self.hash_algorithm.name().as_bytes());
+        write_string(&mut buf, &self.signature.to_bytes());
+        return buf;
+    }
+    /// Parse binary SSHSIG signature
+    pub fn from_bytes(data: &[u8]) -> Result<SshSig, Error> {
+        if !data.starts_with(MAGIC_PREAMBLE) {
+            return Err(Error::InvalidFormat);
+        }
+        let mut cur = Reader::new(&data[MAGIC_PREAMBLE.len()..]);
+        let version = cur.read_u32()?;
+        if version != SIG_VERSION {
+            return Err(Error::UnsupportedType(
+                format!("sshsig version {}", version)));
+        }
+        let public_key = parse_public_blob(cur.read_bytes()?)?;
+        let namespace = cur.read_string()?;
+        let _reserved = cur.read_bytes()?;
+        let hash_name = cur.read_string()?;
+        let hash_algorithm = HashAlgorithm::from_name(hash_name)
+            .ok_or_else(|| Error::UnsupportedType(hash_name.to_string()))?;
+        let signature = Signature::from_bytes(cur.read_bytes()?)?;
+        if !cur.is_empty() {
+            return Err(Error::InvalidFormat);
+        }
+        Ok(SshSig {
+            public_key: public_key,
+            namespace: namespace.to_string(),
+            hash_algorithm: hash_algorithm,
+            signature: signature,
+        })
+    }
+    /// Serialize signature in armored format as `ssh-keygen` does
+    pub fn to_armored(&self) -> String {
+        let data = Base64::encode_string(&self.to_bytes());
+        let mut result = String::with_capacity(data.len() + 80);
+        result.push_str(BEGIN);
+        result.push('\n');
+        for line in data.as_bytes().chunks(LINE_WIDTH) {
+            // base64 is always ascii
+            result.push_str(::core::str::from_utf8(line).unwrap());
+            result.push('\n');
+        }
+        result.push_str(END);
+        result.push('\n');
+        return result;
+    }
+    /// Parse signature in armored format (`-----BEGIN SSH SIGNATURE-----`)
+    pub fn from_armored(data: &str) -> Result<SshSig, Error> {
+        let data = data.trim();
+        if !data.starts_with(BEGIN) || !data.ends_with(END) ||
+            data.len() < BEGIN.len() + END.len()
+        {
+            return Err(Error::InvalidFormat);
+        }
+        let body = &data[BEGIN.len()..data.len() - END.len()];
+        SshSig::from_bytes(&b64decode(body.trim().as_bytes())?)
+    }
+}
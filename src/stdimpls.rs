@@ -1,9 +1,16 @@
-use std::fmt;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::str::FromStr;
 
-use base64;
-use byteorder::{BigEndian, WriteBytesExt};
+use base64ct::{Base64, Encoding};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
 
-use {PublicKey, PrivateKey};
+use openssh::parse_public_key;
+use wire::write_string;
+use {PublicKey, PublicKeyRef, PrivateKey, Error};
 
 
 // We have to implement his manually because Clone doesnt work for [u8; 64]
@@ -15,6 +22,9 @@ impl Clone for PublicKey {
                 Rsa { exponent: exponent.clone(), modulus: modulus.clone() }
             }
             Ed25519(data) => Ed25519(data),
+            Ecdsa { curve, ref point } => {
+                Ecdsa { curve: curve, point: point.clone() }
+            }
         }
     }
 }
@@ -30,6 +40,10 @@ impl PartialEq for PublicKey {
             (&Rsa {..}, _) => false,
             (&Ed25519(ref d1), &Ed25519(ref d2)) => d1 == d2,
             (&Ed25519(..), _) => false,
+            (&Ecdsa { curve: c1, point: ref p1 },
+             &Ecdsa { curve: c2, point: ref p2 })
+            => c1 == c2 && p1 == p2,
+            (&Ecdsa {..}, _) => false,
         }
 
     }
@@ -37,66 +51,192 @@ impl PartialEq for PublicKey {
 
 impl Eq for PublicKey { }
 
-impl fmt::Display for PublicKey {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl Hash for PublicKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_key_ref().hash(state)
+    }
+}
+
+// Must be consistent with the hash of the PublicKey
+impl<'a> Hash for PublicKeyRef<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        use PublicKeyRef::*;
+        match *self {
+            Rsa { exponent, modulus } => {
+                0u8.hash(state);
+                exponent.hash(state);
+                modulus.hash(state);
+            }
+            Ed25519(data) => {
+                1u8.hash(state);
+                data.hash(state);
+            }
+            Ecdsa { curve, point } => {
+                2u8.hash(state);
+                curve.hash(state);
+                point.hash(state);
+            }
+        }
+    }
+}
+
+// Only this direction is implemented: another `PartialEq` impl for
+// `PublicKey` would break type inference in comparisons like `keys == []`
+impl<'a> PartialEq<PublicKey> for PublicKeyRef<'a> {
+    fn eq(&self, other: &PublicKey) -> bool {
+        *self == other.as_key_ref()
+    }
+}
+
+// Keys are ordered by key type, then by the wire blob. Comparing lengths
+// before contents is the same as comparing length-prefixed blob fields.
+impl Ord for PublicKey {
+    fn cmp(&self, other: &PublicKey) -> Ordering {
         use PublicKey::*;
+        match (self, other) {
+            (Rsa { exponent: e1, modulus: n1 },
+             Rsa { exponent: e2, modulus: n2 })
+            => {
+                (e1.len(), e1, n1.len(), n1)
+                    .cmp(&(e2.len(), e2, n2.len(), n2))
+            }
+            (Ed25519(d1), Ed25519(d2)) => d1.cmp(d2),
+            (Ecdsa { curve: c1, point: p1 }, Ecdsa { curve: c2, point: p2 })
+            if c1 == c2
+            => (p1.len(), p1).cmp(&(p2.len(), p2)),
+            _ => self.key_type().cmp(other.key_type()),
+        }
+    }
+}
+
+impl PartialOrd for PublicKey {
+    fn partial_cmp(&self, other: &PublicKey) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<PublicKey, Error> {
+        parse_public_key(s)
+    }
+}
+
+impl PublicKey {
+    /// Writes public key blob in SSH wire format into the buffer
+    pub(crate) fn write_blob(&self, buf: &mut Vec<u8>) {
+        self.as_key_ref().write_blob(buf)
+    }
+    /// Returns public key type as used in openssh (e.g. `ssh-rsa`)
+    pub(crate) fn key_type(&self) -> &'static str {
+        self.as_key_ref().key_type()
+    }
+}
+
+impl<'a> PublicKeyRef<'a> {
+    /// Writes public key blob in SSH wire format into the buffer
+    pub(crate) fn write_blob(&self, buf: &mut Vec<u8>) {
+        self.blob_fields(|field| write_string(buf, field));
+    }
+    /// Calls the function for each string of the public key blob
+    pub(crate) fn blob_fields<F: FnMut(&[u8])>(&self, mut f: F) {
+        use PublicKeyRef::*;
         match *self {
-            Rsa { ref exponent, ref modulus } => {
-                let mut buf = Vec::with_capacity(512);
-                buf.write_u32::<BigEndian>("ssh-rsa".len() as u32).unwrap();
-                buf.extend(b"ssh-rsa");
-                buf.write_u32::<BigEndian>(exponent.len() as u32).unwrap();
-                buf.extend(exponent);
-                buf.write_u32::<BigEndian>(modulus.len() as u32).unwrap();
-                buf.extend(modulus);
-                write!(f, "ssh-rsa {}",
-                    base64::display::Base64Display::standard(&buf))
+            Rsa { exponent, modulus } => {
+                f(b"ssh-rsa");
+                f(exponent);
+                f(modulus);
             }
             Ed25519(data) => {
-                let mut buf = Vec::with_capacity(512);
-                buf.write_u32::<BigEndian>("ssh-ed25519".len() as u32).unwrap();
-                buf.extend(b"ssh-ed25519");
-                buf.write_u32::<BigEndian>(data.len() as u32).unwrap();
-                buf.extend(&data);
-                write!(f, "ssh-ed25519 {}",
-                    base64::display::Base64Display::standard(&buf))
+                f(b"ssh-ed25519");
+                f(data);
             }
+            Ecdsa { curve, point } => {
+                f(curve.key_type().as_bytes());
+                f(curve.name().as_bytes());
+                f(point);
+            }
+        }
+    }
+    /// Returns public key type as used in openssh (e.g. `ssh-rsa`)
+    pub fn key_type(&self) -> &'static str {
+        match *self {
+            PublicKeyRef::Rsa { .. } => "ssh-rsa",
+            PublicKeyRef::Ed25519(..) => "ssh-ed25519",
+            PublicKeyRef::Ecdsa { curve, .. } => curve.key_type(),
         }
     }
 }
 
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut buf = Vec::with_capacity(512);
+        self.write_blob(&mut buf);
+        write!(f, "{} {}", self.key_type(),
+            Base64::encode_string(&buf))
+    }
+}
+
 // We have to implement his manually because Clone doesnt work for [u8; 64]
 impl Clone for PrivateKey {
     fn clone(&self) -> PrivateKey {
         use PrivateKey::*;
         match *self {
-            Ed25519(data) => Ed25519(data),
+            Ed25519(ref data) => Ed25519(*data),
             Rsa { ref n, ref e, ref d, ref iqmp, ref p, ref q }
             => Rsa { n: n.clone(), e: e.clone(), d: d.clone(),
                      iqmp: iqmp.clone(), p: p.clone(), q: q.clone() },
+            Ecdsa { curve, ref point, ref scalar }
+            => Ecdsa { curve: curve, point: point.clone(),
+                       scalar: scalar.clone() },
         }
     }
 }
 
-// We have to implement his manually because PartialEq doesnt work for [u8; 64]
+// Comparison is constant-time (apart from lengths) to not leak secrets
 impl PartialEq for PrivateKey {
     fn eq(&self, other: &PrivateKey) -> bool {
         use PrivateKey::*;
         match (self, other) {
-            (&Rsa { n: ref n1, e: ref e1, d: ref d1, iqmp: ref iqmp1,
-                    p: ref p1, q: ref q1 },
-             &Rsa { n: ref n2, e: ref e2, d: ref d2, iqmp: ref iqmp2,
-                    p: ref p2, q: ref q2 }
+            (Rsa { n: n1, e: e1, d: d1, iqmp: iqmp1, p: p1, q: q1 },
+             Rsa { n: n2, e: e2, d: d2, iqmp: iqmp2, p: p2, q: q2 }
             ) => {
-                n1 == n2 && e1 == e2 && d1 == d2 && iqmp1 == iqmp2 &&
-                p1 == p2 && q1 == q2
+                (n1.ct_eq(n2) & e1.ct_eq(e2) & d1.ct_eq(d2) &
+                 iqmp1.ct_eq(iqmp2) & p1.ct_eq(p2) & q1.ct_eq(q2)).into()
             }
-            (&Rsa {..}, _) => false,
-            (&Ed25519(ref d1), &Ed25519(ref d2)) => &d1[..] == &d2[..],
-            (&Ed25519(..), _) => false,
+            (Rsa {..}, _) => false,
+            (Ed25519(d1), Ed25519(d2)) => d1[..].ct_eq(&d2[..]).into(),
+            (Ed25519(..), _) => false,
+            (Ecdsa { curve: c1, point: p1, scalar: s1 },
+             Ecdsa { curve: c2, point: p2, scalar: s2 })
+            => c1 == c2 && bool::from(p1.ct_eq(p2) & s1.ct_eq(s2)),
+            (Ecdsa {..}, _) => false,
         }
 
     }
 }
 
 impl Eq for PrivateKey { }
+
+impl Drop for PrivateKey {
+    fn drop(&mut self) {
+        use PrivateKey::*;
+        match *self {
+            Rsa { ref mut n, ref mut e, ref mut d, ref mut iqmp,
+                  ref mut p, ref mut q }
+            => {
+                n.zeroize();
+                e.zeroize();
+                d.zeroize();
+                iqmp.zeroize();
+                p.zeroize();
+                q.zeroize();
+            }
+            Ed25519(ref mut data) => data.zeroize(),
+            Ecdsa { ref mut point, ref mut scalar, .. } => {
+                point.zeroize();
+                scalar.zeroize();
+            }
+        }
+    }
+}
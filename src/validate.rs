@@ -0,0 +1,79 @@
+use ed25519_dalek;
+use p256;
+use p384;
+use p521;
+use rsa::BigUint;
+use zeroize::Zeroizing;
+
+use signature::field_bytes;
+use {PrivateKey, EcdsaCurve, Error};
+
+
+impl PrivateKey {
+    /// Check internal consistency of the key
+    ///
+    /// Key files contain redundant values which are not checked when
+    /// parsing. This verifies that for RSA keys `n = p * q`,
+    /// `e * d ≡ 1 (mod lcm(p - 1, q - 1))` and `iqmp * q ≡ 1 (mod p)`, and
+    /// that for Ed25519 and ECDSA keys public part matches the private one.
+    pub fn validate(&self) -> Result<(), Error> {
+        match *self {
+            PrivateKey::Rsa { ref n, ref e, ref d, ref iqmp, ref p, ref q } => {
+                let one = BigUint::from(1u32);
+                let n = BigUint::from_bytes_be(n);
+                let e = BigUint::from_bytes_be(e);
+                let d = Zeroizing::new(BigUint::from_bytes_be(d));
+                let iqmp = Zeroizing::new(BigUint::from_bytes_be(iqmp));
+                let p = Zeroizing::new(BigUint::from_bytes_be(p));
+                let q = Zeroizing::new(BigUint::from_bytes_be(q));
+                if *p <= one || *q <= one || &*p * &*q != n {
+                    return Err(Error::ModulusMismatch);
+                }
+                // congruence modulo lcm is the same as modulo both numbers
+                let ed = Zeroizing::new(&e * &*d);
+                if &*ed % (&*p - &one) != one || &*ed % (&*q - &one) != one {
+                    return Err(Error::PrivateExponentMismatch);
+                }
+                if (&*iqmp * &*q) % &*p != one {
+                    return Err(Error::CoefficientMismatch);
+                }
+            }
+            PrivateKey::Ed25519(ref key) => {
+                let mut seed = Zeroizing::new([0u8; 32]);
+                seed.copy_from_slice(&key[..32]);
+                let key_pair = ed25519_dalek::SigningKey::from_bytes(&seed);
+                if key_pair.verifying_key().as_bytes()[..] != key[32..] {
+                    return Err(Error::PublicKeyMismatch);
+                }
+            }
+            PrivateKey::Ecdsa { curve, ref point, ref scalar } => {
+                let scalar = field_bytes(curve, scalar)?;
+                let public = match curve {
+                    EcdsaCurve::NistP256 => {
+                        p256::ecdsa::SigningKey::from_slice(&scalar)
+                        .map_err(|_| Error::InvalidKey)?
+                        .verifying_key().to_encoded_point(false)
+                        .as_bytes().to_vec()
+                    }
+                    EcdsaCurve::NistP384 => {
+                        p384::ecdsa::SigningKey::from_slice(&scalar)
+                        .map_err(|_| Error::InvalidKey)?
+                        .verifying_key().to_encoded_point(false)
+                        .as_bytes().to_vec()
+                    }
+                    EcdsaCurve::NistP521 => {
+                        let key = p521::ecdsa::SigningKey::from_slice(&scalar)
+                            .map_err(|_| Error::InvalidKey)?;
+                        p521::ecdsa::VerifyingKey::from(&key)
+                        .to_encoded_point(false)
+                        .as_bytes().to_vec()
+                    }
+                };
+                if &public != point {
+                    return Err(Error::PublicKeyMismatch);
+                }
+            }
+        }
+        Ok(())
+    }
+}
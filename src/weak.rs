@@ -0,0 +1,367 @@
+//! Detection of known-weak RSA keys
+//!
+//! The following weaknesses are detected:
+//!
+//! * moduli generated by vulnerable Infineon library (ROCA, CVE-2017-15361)
+//! * moduli having small prime factors
+//! * moduli whose factors are too close to each other, so they can be
+//!   factored with Fermat's method
+//! * keys generated by Debian's broken OpenSSL (CVE-2008-0166), this
+//!   requires a blocklist file (as shipped in `openssh-blacklist` package)
+//!
+//! Only [`check`](fn.check.html) is needed in most cases, but moduli
+//! sharing prime factors with other keys can only be found by looking at
+//! the whole key collection, see [`shared_factors`](fn.shared_factors.html):
+//!
+//! ```rust,ignore
+//! let blocklist = DebianBlocklist::from_file("/usr/share/ssh/blacklist.RSA-2048")?;
+//! for weakness in weak::check(&key, Some(&blocklist)) {
+//!     println!("{}: {}", key, weakness);
+//! }
+//! ```
+use std::collections::{HashMap, HashSet};
+use std::string::String;
+use std::vec::Vec;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use md5::{Md5, Digest};
+use rsa::BigUint;
+
+use {PublicKey, Error};
+
+
+/// Primes used for ROCA fingerprinting (as in the original detector)
+const ROCA_PRIMES: &[u32] = &[
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71,
+    73, 79, 83, 89, 97, 101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151,
+    157, 163, 167,
+];
+
+/// Upper bound of prime factors searched by `small_factor`
+const SMALL_FACTOR_LIMIT: u32 = 1 << 16;
+
+/// Number of Fermat's method iterations
+const FERMAT_ROUNDS: u32 = 100;
+
+
+/// A weakness found in the key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Weakness {
+    /// Modulus is generated by Infineon library vulnerable to ROCA
+    Roca,
+    /// Modulus is divisible by a small prime
+    SmallFactor(u32),
+    /// Modulus is factored with Fermat's method (big-endian factors)
+    #[allow(missing_docs)]
+    Fermat { p: Vec<u8>, q: Vec<u8> },
+    /// Key is in the blocklist of Debian weak keys
+    DebianWeakKey,
+}
+
+/// A pair of RSA keys whose moduli share a prime factor
+///
+/// Both keys are fully broken: `modulus = factor * cofactor` for each of
+/// them. All numbers are big-endian.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedFactor {
+    /// Index of the first key in the input slice
+    pub first: usize,
+    /// Index of the second key in the input slice
+    pub second: usize,
+    /// Common prime factor of both moduli
+    pub factor: Vec<u8>,
+    /// The other factor of the first modulus
+    pub first_cofactor: Vec<u8>,
+    /// The other factor of the second modulus
+    pub second_cofactor: Vec<u8>,
+}
+
+/// Blocklist of keys generated by Debian's OpenSSL with broken RNG
+///
+/// Each line of the file is last 20 hex digits of the MD5 fingerprint of
+/// the key, lines starting with `#` are comments.
+#[derive(Debug, Clone, Default)]
+pub struct DebianBlocklist {
+    entries: HashSet<[u8; 10]>,
+}
+
+impl DebianBlocklist {
+    /// Parse blocklist file contents
+    pub fn parse(data: &str) -> Result<DebianBlocklist, Error> {
+        let mut entries = HashSet::new();
+        for line in data.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // full fingerprints are also accepted
+            if line.len() != 20 && line.len() != 32 {
+                return Err(Error::InvalidFormat);
+            }
+            let hex = &line[line.len() - 20..];
+            let mut entry = [0u8; 10];
+            for (idx, byte) in entry.iter_mut().enumerate() {
+                *byte = hex.get(idx*2..idx*2+2)
+                    .and_then(|x| u8::from_str_radix(x, 16).ok())
+                    .ok_or(Error::InvalidFormat)?;
+            }
+            entries.insert(entry);
+        }
+        Ok(DebianBlocklist { entries: entries })
+    }
+    /// Read blocklist file (e.g. `/usr/share/ssh/blacklist.RSA-2048`)
+    pub fn from_file<P: AsRef<Path>>(path: P)
+        -> Result<DebianBlocklist, Error>
+    {
+        let mut buf = String::with_capacity(1 << 20);
+        File::open(path)?.read_to_string(&mut buf)?;
+        DebianBlocklist::parse(&buf)
+    }
+    /// Returns true if the key is in the blocklist
+    pub fn contains(&self, key: &PublicKey) -> bool {
+        let mut blob = Vec::with_capacity(512);
+        key.write_blob(&mut blob);
+        let hash = Md5::digest(&blob);
+        let mut entry = [0u8; 10];
+        entry.copy_from_slice(&hash[6..]);
+        self.entries.contains(&entry)
+    }
+    /// Number of entries in the blocklist
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    /// Returns true if blocklist has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Run all checks on the key
+///
+/// Debian blocklist is only checked if it's passed. Keys other than RSA
+/// are only checked against the blocklist.
+pub fn check(key: &PublicKey, blocklist: Option<&DebianBlocklist>)
+    -> Vec<Weakness>
+{
+    let mut result = Vec::new();
+    if is_roca(key) {
+        result.push(Weakness::Roca);
+    }
+    if let Some(factor) = small_factor(key) {
+        result.push(Weakness::SmallFactor(factor));
+    } else if let Some((p, q)) = fermat_factor(key) {
+        result.push(Weakness::Fermat { p: p, q: q });
+    }
+    if blocklist.map(|b| b.contains(key)).unwrap_or(false) {
+        result.push(Weakness::DebianWeakKey);
+    }
+    return result;
+}
+
+/// Returns true if RSA modulus has the structure of ROCA-vulnerable keys
+///
+/// Such moduli have residues modulo small primes which are all powers
+/// of 65537.
+pub fn is_roca(key: &PublicKey) -> bool {
+    let modulus = match *key {
+        PublicKey::Rsa { ref modulus, .. } => modulus,
+        _ => return false,
+    };
+    if modulus.iter().all(|&x| x == 0) {
+        return false;
+    }
+    ROCA_PRIMES.iter().all(|&prime| {
+        let residue = mod_small(modulus, prime);
+        let generator = 65537 % prime;
+        let mut power = 1;
+        loop {
+            if power == residue {
+                return true;
+            }
+            power = power * generator % prime;
+            if power == 1 {
+                return false;
+            }
+        }
+    })
+}
+
+/// Returns the smallest prime factor of RSA modulus below 2^16, if any
+pub fn small_factor(key: &PublicKey) -> Option<u32> {
+    let modulus = match *key {
+        PublicKey::Rsa { ref modulus, .. } => modulus,
+        _ => return None,
+    };
+    return small_primes().iter().cloned()
+        .find(|&prime| mod_small(modulus, prime) == 0);
+}
+
+/// Primes below `SMALL_FACTOR_LIMIT`, sieved once on first use
+fn small_primes() -> &'static [u32] {
+    static PRIMES: OnceLock<Vec<u32>> = OnceLock::new();
+    PRIMES.get_or_init(|| {
+        let mut sieve = vec![true; SMALL_FACTOR_LIMIT as usize];
+        let mut primes = Vec::new();
+        for num in 2..SMALL_FACTOR_LIMIT {
+            if !sieve[num as usize] {
+                continue;
+            }
+            let mut multiple = num * num;
+            while multiple < SMALL_FACTOR_LIMIT {
+                sieve[multiple as usize] = false;
+                multiple += num;
+            }
+            primes.push(num);
+        }
+        primes
+    })
+}
+
+/// Factors RSA modulus if its prime factors are close to each other
+///
+/// Returns big-endian `(p, q)`, where `p < q`.
+pub fn fermat_factor(key: &PublicKey) -> Option<(Vec<u8>, Vec<u8>)> {
+    let modulus = match *key {
+        PublicKey::Rsa { ref modulus, .. } => BigUint::from_bytes_be(modulus),
+        _ => return None,
+    };
+    let one = BigUint::from(1u32);
+    if modulus <= one {
+        return None;
+    }
+    let mut a = modulus.sqrt();
+    if &a * &a < modulus {
+        a += &one;
+    }
+    for _ in 0..FERMAT_ROUNDS {
+        let b2 = &a * &a - &modulus;
+        let b = b2.sqrt();
+        if &b * &b == b2 {
+            let p = &a - &b;
+            if p <= one {
+                // n = 1 * n, which is not a factorization
+                return None;
+            }
+            return Some((p.to_bytes_be(), (&a + &b).to_bytes_be()));
+        }
+        a += &one;
+    }
+    return None;
+}
+
+/// Finds all pairs of RSA keys which share a prime factor
+///
+/// This uses batch GCD algorithm: moduli are multiplied in a product tree,
+/// then the product is reduced modulo squares of the moduli using a
+/// remainder tree, which is much faster than computing GCD of each pair.
+///
+/// Keys other than RSA are skipped. The same modulus occurring multiple
+/// times in the input is reported only once, with the index of its first
+/// occurrence. Results are sorted by indices.
+pub fn shared_factors(keys: &[PublicKey]) -> Vec<SharedFactor> {
+    let mut indices = Vec::new();
+    let mut moduli = Vec::new();
+    let mut seen = HashMap::new();
+    for (idx, key) in keys.iter().enumerate() {
+        if let PublicKey::Rsa { ref modulus, .. } = *key {
+            let start = modulus.iter().position(|&x| x != 0)
+                .unwrap_or(modulus.len());
+            if modulus.len() - start < 2 {
+                continue;  // 0 and 1 can't share anything
+            }
+            seen.entry(&modulus[start..]).or_insert_with(|| {
+                indices.push(idx);
+                moduli.push(BigUint::from_bytes_be(&modulus[start..]));
+            });
+        }
+    }
+    if moduli.len() < 2 {
+        return Vec::new();
+    }
+
+    let mut tree = vec![moduli];
+    while tree[tree.len() - 1].len() > 1 {
+        let next = tree[tree.len() - 1].chunks(2).map(|pair| match *pair {
+            [ref a, ref b] => a * b,
+            [ref a] => a.clone(),
+            _ => unreachable!(),
+        }).collect();
+        tree.push(next);
+    }
+    let mut remainders = tree[tree.len() - 1].clone();
+    for level in tree.iter().rev().skip(1) {
+        remainders = level.iter().enumerate()
+            .map(|(idx, value)| &remainders[idx / 2] % (value * value))
+            .collect();
+    }
+    let moduli = &tree[0];
+
+    // gcd(n, (product / n) mod n) is a product of primes shared with others
+    let one = BigUint::from(1u32);
+    let vulnerable = moduli.iter().zip(&remainders).enumerate()
+        .filter(|&(_, (n, rem))| gcd(n.clone(), rem / n) != one)
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>();
+
+    // few keys are vulnerable usually, so pairwise check is fine here
+    let mut result = Vec::new();
+    for (pos, &i) in vulnerable.iter().enumerate() {
+        for &j in &vulnerable[pos+1..] {
+            let factor = gcd(moduli[i].clone(), moduli[j].clone());
+            if factor == one {
+                continue;
+            }
+            result.push(SharedFactor {
+                first: indices[i],
+                second: indices[j],
+                first_cofactor: (&moduli[i] / &factor).to_bytes_be(),
+                second_cofactor: (&moduli[j] / &factor).to_bytes_be(),
+                factor: factor.to_bytes_be(),
+            });
+        }
+    }
+    return result;
+}
+
+fn gcd(mut a: BigUint, mut b: BigUint) -> BigUint {
+    let zero = BigUint::from(0u32);
+    while b != zero {
+        let rem = &a % &b;
+        a = b;
+        b = rem;
+    }
+    return a;
+}
+
+/// Remainder of division of big-endian number by a small number
+fn mod_small(value: &[u8], divisor: u32) -> u32 {
+    let divisor = divisor as u64;
+    let mut result = 0;
+    for &byte in value {
+        result = ((result << 8) | byte as u64) % divisor;
+    }
+    return result as u32;
+}
+
+impl fmt::Display for Weakness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Weakness::Roca => {
+                write!(f, "key is vulnerable to ROCA (CVE-2017-15361)")
+            }
+            Weakness::SmallFactor(factor) => {
+                write!(f, "modulus is divisible by {}", factor)
+            }
+            Weakness::Fermat { .. } => {
+                write!(f, "modulus is factorable with Fermat's method")
+            }
+            Weakness::DebianWeakKey => {
+                write!(f, "key is a Debian weak key (CVE-2008-0166)")
+            }
+        }
+    }
+}
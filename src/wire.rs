@@ -0,0 +1,267 @@
+//! SSH wire format encoding
+//!
+//! Implements data types described in section 5 of
+//! [RFC 4251](https://tools.ietf.org/html/rfc4251#section-5): `byte`,
+//! `boolean`, `uint32`, `uint64`, `string`, `mpint` and `name-list`.
+//!
+//! Only non-negative `mpint` values are supported, as there are no negative
+//! numbers in keys and signatures. They are passed around as big-endian
+//! bytes without the sign byte.
+use alloc::vec::Vec;
+use core::str::from_utf8;
+
+use byteorder::{BigEndian, ByteOrder};
+
+use openssh::{ParseOptions, parse_public_blob};
+use {PublicKey, PublicKeyRef, Error};
+
+
+/// Reader of values encoded in SSH wire format
+///
+/// All errors are reported as `Error::InvalidFormat`.
+#[derive(Debug, Clone)]
+pub struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+/// Writer of values in SSH wire format
+#[derive(Debug, Clone, Default)]
+pub struct Writer {
+    buf: Vec<u8>,
+}
+
+impl<'a> Reader<'a> {
+    /// Create reader at the start of the buffer
+    pub fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader {
+            data: data,
+            offset: 0,
+        }
+    }
+    /// Read `byte`
+    pub fn read_byte(&mut self) -> Result<u8, Error> {
+        let byte = *self.data.get(self.offset).ok_or(Error::InvalidFormat)?;
+        self.offset += 1;
+        return Ok(byte);
+    }
+    /// Read `boolean` (any non-zero value is true)
+    pub fn read_bool(&mut self) -> Result<bool, Error> {
+        Ok(self.read_byte()? != 0)
+    }
+    /// Read `uint32`
+    pub fn read_u32(&mut self) -> Result<u32, Error> {
+        let cur = &self.data[self.offset..];
+        if cur.len() < 4 {
+            return Err(Error::InvalidFormat);
+        }
+        self.offset += 4;
+        return Ok(BigEndian::read_u32(&cur[..4]));
+    }
+    /// Read `uint64`
+    pub fn read_u64(&mut self) -> Result<u64, Error> {
+        let cur = &self.data[self.offset..];
+        if cur.len() < 8 {
+            return Err(Error::InvalidFormat);
+        }
+        self.offset += 8;
+        return Ok(BigEndian::read_u64(&cur[..8]));
+    }
+    /// Read `string` as raw bytes
+    pub fn read_bytes(&mut self) -> Result<&'a [u8], Error> {
+        let cur = &self.data[self.offset..];
+        if cur.len() < 4 {
+            return Err(Error::InvalidFormat);
+        }
+        let len = BigEndian::read_u32(&cur[..4]) as usize;
+        if cur.len() - 4 < len {
+            return Err(Error::InvalidFormat);
+        }
+        self.offset += len + 4;
+        return Ok(&cur[4..len+4]);
+    }
+    /// Read `string` which must be valid UTF-8
+    pub fn read_string(&mut self) -> Result<&'a str, Error> {
+        from_utf8(self.read_bytes()?)
+        .map_err(|_| Error::InvalidFormat)
+    }
+    /// Read `mpint` which must be non-negative, strips leading zeros
+    ///
+    /// Like openssh, redundant leading zeros are accepted, use
+    /// [`read_mpint_strict`](#method.read_mpint_strict) to reject them.
+    pub fn read_mpint(&mut self) -> Result<&'a [u8], Error> {
+        let data = self.read_bytes()?;
+        if data.first().map(|&x| x & 0x80 != 0).unwrap_or(false) {
+            return Err(Error::InvalidFormat);
+        }
+        let start = data.iter().position(|&x| x != 0).unwrap_or(data.len());
+        return Ok(&data[start..]);
+    }
+    /// Read `mpint` which must be non-negative and minimally encoded
+    ///
+    /// The only leading zero allowed is the one before the byte with the
+    /// highest bit set, as required by RFC 4251. It is stripped.
+    pub fn read_mpint_strict(&mut self) -> Result<&'a [u8], Error> {
+        let data = self.read_bytes()?;
+        if data.first().map(|&x| x & 0x80 != 0).unwrap_or(false) {
+            return Err(Error::InvalidFormat);
+        }
+        if data.first() == Some(&0) {
+            if data.get(1).map(|&x| x & 0x80 == 0).unwrap_or(true) {
+                // non-minimal encoding, zero is an empty string
+                return Err(Error::InvalidFormat);
+            }
+            return Ok(&data[1..]);
+        }
+        return Ok(data);
+    }
+    /// Read `name-list`
+    pub fn read_name_list(&mut self) -> Result<Vec<&'a str>, Error> {
+        let value = self.read_string()?;
+        if value.is_empty() {
+            return Ok(Vec::new());
+        }
+        let names = value.split(',').collect::<Vec<_>>();
+        if names.iter().any(|name| name.is_empty()) {
+            return Err(Error::InvalidFormat);
+        }
+        return Ok(names);
+    }
+    /// Returns true if all data is read
+    pub fn is_empty(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+    /// Number of bytes consumed so far
+    pub fn position(&self) -> usize {
+        self.offset
+    }
+    /// Data which isn't read yet
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.data[self.offset..]
+    }
+}
+
+impl Writer {
+    /// Create empty writer
+    pub fn new() -> Writer {
+        Writer::default()
+    }
+    /// Write `byte`
+    pub fn write_byte(&mut self, value: u8) -> &mut Self {
+        self.buf.push(value);
+        self
+    }
+    /// Write `boolean`
+    pub fn write_bool(&mut self, value: bool) -> &mut Self {
+        self.buf.push(value as u8);
+        self
+    }
+    /// Write `uint32`
+    pub fn write_u32(&mut self, value: u32) -> &mut Self {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+    /// Write `uint64`
+    pub fn write_u64(&mut self, value: u64) -> &mut Self {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+    /// Write `string` from raw bytes
+    pub fn write_bytes(&mut self, value: &[u8]) -> &mut Self {
+        write_string(&mut self.buf, value);
+        self
+    }
+    /// Write `string`
+    pub fn write_string(&mut self, value: &str) -> &mut Self {
+        write_string(&mut self.buf, value.as_bytes());
+        self
+    }
+    /// Write non-negative `mpint` from big-endian bytes
+    ///
+    /// Leading zeros are stripped and a zero byte is prepended if the
+    /// highest bit is set, so that the number isn't treated as negative.
+    pub fn write_mpint(&mut self, value: &[u8]) -> &mut Self {
+        write_mpint(&mut self.buf, value);
+        self
+    }
+    /// Write `name-list`
+    ///
+    /// Names must be non-empty and must not contain commas.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any name is empty or contains a comma, as such list can't
+    /// be read back.
+    pub fn write_name_list<S: AsRef<str>>(&mut self, names: &[S])
+        -> &mut Self
+    {
+        let names = names.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+        assert!(names.iter().all(|x| !x.is_empty() && !x.contains(',')),
+                "invalid name in name-list");
+        let value = names.join(",");
+        write_string(&mut self.buf, value.as_bytes());
+        self
+    }
+    /// Data written so far
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+    /// Return the underlying buffer
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+impl PublicKey {
+    /// Public key blob in SSH wire format (as used in SSH protocol)
+    pub fn to_wire_blob(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(512);
+        self.write_blob(&mut buf);
+        return buf;
+    }
+    /// Parse public key from the blob in SSH wire format
+    ///
+    /// Key type is determined by the type string embedded in the blob, as
+    /// there is no textual prefix like in `.pub` files.
+    pub fn from_wire_blob(data: &[u8]) -> Result<PublicKey, Error> {
+        parse_public_blob(data)
+    }
+}
+
+impl<'a> PublicKeyRef<'a> {
+    /// Public key blob in SSH wire format (as used in SSH protocol)
+    pub fn to_wire_blob(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(512);
+        self.write_blob(&mut buf);
+        return buf;
+    }
+    /// Parse public key from the blob in SSH wire format without copying
+    pub fn from_wire_blob(data: &'a [u8]) -> Result<PublicKeyRef<'a>, Error> {
+        ParseOptions::new().parse_public_blob_ref(data)
+    }
+}
+
+pub(crate) fn write_string(buf: &mut Vec<u8>, data: &[u8]) {
+    buf.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    buf.extend(data);
+}
+
+/// Length of the `mpint` as written by `write_mpint`, including the prefix
+pub(crate) fn mpint_len(value: &[u8]) -> usize {
+    let start = value.iter().position(|&x| x != 0).unwrap_or(value.len());
+    let value = &value[start..];
+    let sign = value.first().map(|&x| x & 0x80 != 0).unwrap_or(false);
+    return 4 + sign as usize + value.len();
+}
+
+pub(crate) fn write_mpint(buf: &mut Vec<u8>, value: &[u8]) {
+    let start = value.iter().position(|&x| x != 0).unwrap_or(value.len());
+    let value = &value[start..];
+    if value.first().map(|&x| x & 0x80 != 0).unwrap_or(false) {
+        buf.extend_from_slice(&(value.len() as u32 + 1).to_be_bytes());
+        buf.push(0);
+    } else {
+        buf.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    }
+    buf.extend(value);
+}
@@ -0,0 +1,16 @@
+# revocations used to generate revoked.krl
+serial: 1-10
+serial: 100
+serial: 1000
+serial: 1002
+serial: 1003
+serial: 1005
+serial: 1010
+serial: 1012
+id: leaked-key
+id: old laptop
+serial: 5000-100000
+sha1: ssh-rsa AAAAB3NzaC1yc2EAAAADAQABAAAAgQDApmvwhoDOXRlAl/lhWuFIuQ+hsjkgYBTJMh4xpkK1Uup8mhd/ybbkT1Okx1UDcvYrmqQkYTbbrwnt5LtwTl9oYA8Q4XzWZf9XOmLpnQ9YL4srsaG+vAgmGKgFl9Vo5En7QoTiqOD74sQxKRI+YgBAIuvZxsdVDyK6x3dhXHW7Mw== pc@dish
+sha256: ecdsa-sha2-nistp521 AAAAE2VjZHNhLXNoYTItbmlzdHA1MjEAAAAIbmlzdHA1MjEAAACFBAAkWPAc9J8zpXF7j4kLKU0KTAre1FEWrycEXrJFpeB6bmsCKLTW+O4gEkIijz08OTHkDWCQ0Fm9EpADvbiqUT0W8QAdr6h6+pHUb2IcptcNfzIaqsBpM7OljeTAahhhXAYsD5VFg40Yhqjh8632x/wz6i/TuzvYrPgpfWUTcmuFwwIWcA== test@ssh-keys
+hash: SHA256:HIHlI25MXooP0gHXUQ4xCuieEVtlmrtpbWGzwVXoYI4
+ssh-rsa AAAAB3NzaC1yc2EAAAADAQABAAABAQDAZTJPjWz4Cl5iYMISrw/FA50MBiZAjHTvi0jUuGUrwLEwpX8AqPVi9AufnMkgyy0ILuYBXHB7bedeLJWwADdm8bZf3YT3UylbID6+zu9dYqATEzWQg0fNBA3Ly+t3h4eBwx8sVbldWk0flCy+lWIw1kK/c8N1L0AUoweXz9YasEwCoUPryYYjkXGGyb++FDfS486mjptt+vQS7ohl7Cuc2FpeGRBg5G9o8T6/DvDXqqJNp1bKUf70RBtXu5R+QnJCYNyXsODgt/I/aD8NosjMPPsHuqQ8T9fIaDxIUnK1a0NWOKJ/0Mfct8hBbHjpxuVMUFuGnJu9xpo7WXVpVMfb pc@dish
@@ -0,0 +1,196 @@
+#![cfg(all(unix, feature="std"))]
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Child, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+extern crate ssh_keys;
+
+use ssh_keys::{PrivateKey, PublicKey, Error, SignatureAlgorithm};
+use ssh_keys::agent::{Client, Constraint, RSA_SHA2_256};
+
+
+fn read_file<T: AsRef<Path>>(path: T) -> String {
+    let mut f = File::open(path).unwrap();
+    let mut buf = String::with_capacity(128);
+    f.read_to_string(&mut buf).unwrap();
+    buf
+}
+
+fn private_key<T: AsRef<Path>>(path: T) -> PrivateKey {
+    ssh_keys::openssh::parse_private_key(&read_file(path))
+        .unwrap().remove(0)
+}
+
+fn public_key<T: AsRef<Path>>(path: T) -> PublicKey {
+    ssh_keys::openssh::parse_public_key(&read_file(path)).unwrap()
+}
+
+/// Stream returning canned replies and recording requests
+struct Mock {
+    input: io::Cursor<Vec<u8>>,
+    output: Vec<u8>,
+}
+
+impl Mock {
+    fn new(replies: &[&[u8]]) -> Mock {
+        let mut input = Vec::new();
+        for reply in replies {
+            input.extend(&(reply.len() as u32).to_be_bytes());
+            input.extend(*reply);
+        }
+        Mock { input: io::Cursor::new(input), output: Vec::new() }
+    }
+}
+
+impl Read for Mock {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.input.read(buf)
+    }
+}
+
+impl Write for Mock {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct Agent {
+    process: Child,
+    dir: PathBuf,
+}
+
+impl Agent {
+    fn spawn(name: &str) -> Option<Agent> {
+        let dir = env::temp_dir().join(
+            format!("ssh-keys-agent-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let sock = dir.join("agent.sock");
+        let process = match Command::new("ssh-agent")
+            .arg("-D").arg("-a").arg(&sock)
+            .stdout(Stdio::null()).stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(process) => process,
+            Err(_) => return None,  // no ssh-agent installed, skip
+        };
+        for _ in 0..100 {
+            if sock.exists() {
+                break;
+            }
+            sleep(Duration::from_millis(10));
+        }
+        Some(Agent { process, dir })
+    }
+    fn connect(&self) -> Client<std::os::unix::net::UnixStream> {
+        Client::connect(self.dir.join("agent.sock")).unwrap()
+    }
+}
+
+impl Drop for Agent {
+    fn drop(&mut self) {
+        self.process.kill().ok();
+        self.process.wait().ok();
+        fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+fn write_string(buf: &mut Vec<u8>, data: &[u8]) {
+    buf.extend(&(data.len() as u32).to_be_bytes());
+    buf.extend(data);
+}
+
+#[test]
+fn mock_list_identities() {
+    let key = public_key("test-keys/ed25519.pub");
+    let raw = match key {
+        PublicKey::Ed25519(raw) => raw,
+        _ => unreachable!(),
+    };
+    let mut key_blob = Vec::new();
+    write_string(&mut key_blob, b"ssh-ed25519");
+    write_string(&mut key_blob, &raw);
+    let mut reply = vec![12, 0, 0, 0, 2];
+    write_string(&mut reply, b"\0\0\0\x0bssh-unknown");
+    write_string(&mut reply, b"other");
+    write_string(&mut reply, &key_blob);
+    write_string(&mut reply, b"test");
+    let mut client = Client::new(Mock::new(&[&reply]));
+    let ids = client.list_identities().unwrap();
+    assert_eq!(ids.len(), 1);
+    assert_eq!(ids[0].key, key);
+    assert_eq!(ids[0].comment, "test");
+    assert_eq!(client.into_inner().output, vec![0, 0, 0, 1, 11]);
+}
+
+#[test]
+fn mock_failure() {
+    let mut client = Client::new(Mock::new(&[&[5]]));
+    match client.remove_all_identities() {
+        Err(Error::AgentFailure) => {}
+        r => panic!("unexpected result {:?}", r),
+    }
+    let mut client = Client::new(Mock::new(&[]));
+    client.remove_all_identities().unwrap_err();
+}
+
+#[test]
+fn ssh_agent() {
+    let agent = match Agent::spawn("full") {
+        Some(agent) => agent,
+        None => return,
+    };
+    let mut client = agent.connect();
+    assert_eq!(client.list_identities().unwrap(), vec![]);
+    for name in &["ed25519", "rsa2048", "ecdsa256", "ecdsa384", "ecdsa521"] {
+        let key = private_key(format!("test-keys/{}", name));
+        client.add_identity(&key, name, &[]).unwrap();
+    }
+    let ids = client.list_identities().unwrap();
+    assert_eq!(ids.len(), 5);
+    assert_eq!(ids[1].key, public_key("test-keys/rsa2048.pub"));
+    assert_eq!(ids[1].comment, "rsa2048");
+    for id in &ids {
+        let sig = client.sign(&id.key, b"hello", RSA_SHA2_256).unwrap();
+        id.key.verify_strict(b"hello", &sig).unwrap();
+    }
+    let sig = client.sign(&ids[1].key, b"hello", RSA_SHA2_256).unwrap();
+    assert_eq!(sig.algorithm(), SignatureAlgorithm::RsaSha256);
+
+    client.remove_identity(&ids[0].key).unwrap();
+    assert_eq!(client.list_identities().unwrap().len(), 4);
+    client.remove_identity(&ids[0].key).unwrap_err();
+
+    client.lock("secret").unwrap();
+    assert_eq!(client.list_identities().unwrap(), vec![]);
+    client.unlock("wrong").unwrap_err();
+    client.unlock("secret").unwrap();
+    assert_eq!(client.list_identities().unwrap().len(), 4);
+
+    client.remove_all_identities().unwrap();
+    assert_eq!(client.list_identities().unwrap(), vec![]);
+}
+
+#[test]
+fn ssh_agent_constrained() {
+    let agent = match Agent::spawn("constrained") {
+        Some(agent) => agent,
+        None => return,
+    };
+    let mut client = agent.connect();
+    let key = private_key("test-keys/ed25519");
+    client.add_identity(&key, "limited", &[Constraint::Lifetime(3600)])
+        .unwrap();
+    let ids = client.list_identities().unwrap();
+    assert_eq!(ids.len(), 1);
+    assert_eq!(ids[0].key, key.public_key());
+    let unknown = Constraint::Extension("unknown@example.com".into(), vec![]);
+    client.add_identity(&key, "unknown", &[unknown]).unwrap_err();
+}
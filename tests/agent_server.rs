@@ -0,0 +1,351 @@
+#![cfg(all(unix, feature="std"))]
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+extern crate ssh_keys;
+
+use ssh_keys::{PrivateKey, PublicKey, SignatureAlgorithm};
+use ssh_keys::agent::{Client, Server, Session, MemoryStore, KeyStore};
+use ssh_keys::agent::Constraint;
+use ssh_keys::agent::{DestinationConstraint, Hop, HostKey, RSA_SHA2_512};
+
+
+fn read_file<T: AsRef<Path>>(path: T) -> String {
+    let mut f = File::open(path).unwrap();
+    let mut buf = String::with_capacity(128);
+    f.read_to_string(&mut buf).unwrap();
+    buf
+}
+
+fn private_key<T: AsRef<Path>>(path: T) -> PrivateKey {
+    ssh_keys::openssh::parse_private_key(&read_file(path))
+        .unwrap().remove(0)
+}
+
+fn public_key<T: AsRef<Path>>(path: T) -> PublicKey {
+    ssh_keys::openssh::parse_public_key(&read_file(path)).unwrap()
+}
+
+fn write_string(buf: &mut Vec<u8>, data: &[u8]) {
+    buf.extend(&(data.len() as u32).to_be_bytes());
+    buf.extend(data);
+}
+
+fn blob(key: &PublicKey) -> Vec<u8> {
+    let text = key.to_string();
+    decode_base64(text.split_whitespace().nth(1).unwrap())
+}
+
+fn decode_base64(data: &str) -> Vec<u8> {
+    const ALPHABET: &[u8] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut bits = 0u32;
+    let mut nbits = 0;
+    let mut result = Vec::new();
+    for c in data.bytes().take_while(|&c| c != b'=') {
+        let val = ALPHABET.iter().position(|&x| x == c).unwrap() as u32;
+        bits = (bits << 6) | val;
+        nbits += 6;
+        if nbits >= 8 {
+            nbits -= 8;
+            result.push((bits >> nbits) as u8);
+        }
+    }
+    result
+}
+
+fn connected_client() -> Client<UnixStream> {
+    let (client, server) = UnixStream::pair().unwrap();
+    thread::spawn(move || {
+        Server::new(MemoryStore::new()).serve(server).unwrap();
+    });
+    Client::new(client)
+}
+
+#[test]
+fn client_roundtrip() {
+    let mut client = connected_client();
+    assert_eq!(client.list_identities().unwrap(), vec![]);
+    for name in &["ed25519", "rsa2048", "ecdsa384"] {
+        let key = private_key(format!("test-keys/{}", name));
+        client.add_identity(&key, name, &[]).unwrap();
+    }
+    let ids = client.list_identities().unwrap();
+    assert_eq!(ids.len(), 3);
+    assert_eq!(ids[1].key, public_key("test-keys/rsa2048.pub"));
+    assert_eq!(ids[1].comment, "rsa2048");
+    for id in &ids {
+        let sig = client.sign(&id.key, b"data", RSA_SHA2_512).unwrap();
+        id.key.verify_strict(b"data", &sig).unwrap();
+    }
+    let sig = client.sign(&ids[1].key, b"data", 0).unwrap();
+    assert_eq!(sig.algorithm(), SignatureAlgorithm::RsaSha1);
+
+    client.lock("secret").unwrap();
+    assert_eq!(client.list_identities().unwrap(), vec![]);
+    client.sign(&ids[0].key, b"data", 0).unwrap_err();
+    client.lock("other").unwrap_err();
+    client.unlock("wrong").unwrap_err();
+    client.unlock("secret").unwrap();
+    client.unlock("secret").unwrap_err();
+
+    client.remove_identity(&ids[0].key).unwrap();
+    client.remove_identity(&ids[0].key).unwrap_err();
+    client.sign(&ids[0].key, b"data", 0).unwrap_err();
+    client.remove_all_identities().unwrap();
+    assert_eq!(client.list_identities().unwrap(), vec![]);
+}
+
+#[test]
+fn constraints() {
+    let mut server = Server::new(MemoryStore::new());
+    server.set_confirm(|key| key.comment == "allowed");
+    let server = Arc::new(server);
+    let (client, stream) = UnixStream::pair().unwrap();
+    let srv = server.clone();
+    thread::spawn(move || srv.serve(stream).unwrap());
+    let mut client = Client::new(client);
+
+    let ed = private_key("test-keys/ed25519");
+    let ecdsa = private_key("test-keys/ecdsa256");
+    client.add_identity(&ed, "allowed", &[Constraint::Confirm]).unwrap();
+    client.add_identity(&ecdsa, "denied", &[Constraint::Confirm]).unwrap();
+    client.sign(&ed.public_key(), b"data", 0).unwrap();
+    client.sign(&ecdsa.public_key(), b"data", 0).unwrap_err();
+
+    client.add_identity(&ecdsa, "expired", &[Constraint::Lifetime(0)])
+        .unwrap();
+    let ids = client.list_identities().unwrap();
+    assert_eq!(ids.len(), 1);
+    assert_eq!(ids[0].comment, "allowed");
+
+    let unknown = Constraint::Extension("unknown@example.com".into(), vec![]);
+    client.add_identity(&ecdsa, "unknown", &[unknown]).unwrap_err();
+}
+
+#[test]
+fn unlock_delay() {
+    let mut client = connected_client();
+    client.lock("secret").unwrap();
+    let start = Instant::now();
+    client.unlock("wrong").unwrap_err();
+    client.unlock("wrong").unwrap_err();
+    // 100ms after the first failure, 200ms after the second one
+    assert!(start.elapsed() >= Duration::from_millis(300));
+    client.unlock("secret").unwrap();
+}
+
+#[test]
+fn preloaded_store() {
+    let key = private_key("test-keys/ed25519");
+    let public = key.public_key();
+    let mut store = MemoryStore::new();
+    store.add(key, "preloaded".into()).unwrap();
+    assert_eq!(store.find(&public).unwrap(), Some("preloaded".into()));
+    assert_eq!(store.identities().unwrap(),
+               vec![(public.clone(), "preloaded".into())]);
+    let server = Server::new(store);
+    let mut session = Session::new();
+    let reply = server.handle(&mut session, &sign_request(&public, b"data"));
+    assert_eq!(reply[0], 14);
+}
+
+fn session_bind(host: &PrivateKey, session_id: &[u8], forwarded: bool)
+    -> Vec<u8>
+{
+    let host_key = host.public_key();
+    let alg = SignatureAlgorithm::EcdsaNistP256;
+    let mut msg = vec![27];
+    write_string(&mut msg, b"session-bind@openssh.com");
+    write_string(&mut msg, &blob(&host_key));
+    write_string(&mut msg, session_id);
+    write_string(&mut msg, &host.sign(session_id, alg).unwrap().to_bytes());
+    msg.push(forwarded as u8);
+    msg
+}
+
+fn userauth_request(session_id: &[u8], user: &str, key: &PublicKey,
+                    host_key: &PublicKey)
+    -> Vec<u8>
+{
+    let mut data = Vec::new();
+    write_string(&mut data, session_id);
+    data.push(50);
+    write_string(&mut data, user.as_bytes());
+    write_string(&mut data, b"ssh-connection");
+    write_string(&mut data, b"publickey-hostbound-v00@openssh.com");
+    data.push(1);
+    write_string(&mut data, b"ssh-ed25519");
+    write_string(&mut data, &blob(key));
+    write_string(&mut data, &blob(host_key));
+    data
+}
+
+fn sign_request(key: &PublicKey, data: &[u8]) -> Vec<u8> {
+    let mut msg = vec![13];
+    write_string(&mut msg, &blob(key));
+    write_string(&mut msg, data);
+    msg.extend(&[0, 0, 0, 0]);
+    msg
+}
+
+fn count_identities(server: &Server<MemoryStore>, session: &mut Session)
+    -> u8
+{
+    let reply = server.handle(session, &[11]);
+    assert_eq!(reply[0], 12);
+    reply[4]
+}
+
+#[test]
+fn destination_constraints() {
+    let host = private_key("test-keys/ecdsa256");
+    let user_key = private_key("test-keys/ed25519");
+    let key = user_key.public_key();
+    let server = Server::new(MemoryStore::new());
+    let destination = DestinationConstraint {
+        from: Hop::default(),
+        to: Hop {
+            user: Some("git".into()),
+            hostname: Some("example.com".into()),
+            host_keys: vec![HostKey { key: host.public_key(), is_ca: false }],
+        },
+    };
+    // the request is recorded, but there is no reply
+    let mut capture = Client::new(Capture(Vec::new()));
+    capture.add_identity(&user_key, "restricted",
+        &[Constraint::RestrictDestination(vec![destination])]).unwrap_err();
+    let add = capture.into_inner().0.split_off(4);
+    let mut session = Session::new();
+    assert_eq!(server.handle(&mut session, &add), vec![6]);
+
+    // local use
+    assert_eq!(count_identities(&server, &mut session), 1);
+
+    // connection to the permitted host
+    let mut session = Session::new();
+    assert_eq!(server.handle(&mut session, &session_bind(&host, b"sid", false)),
+               vec![6]);
+    assert_eq!(count_identities(&server, &mut session), 1);
+    let data = userauth_request(b"sid", "git", &key, &host.public_key());
+    assert_eq!(server.handle(&mut session, &sign_request(&key, &data))[0], 14);
+    let data = userauth_request(b"sid", "root", &key, &host.public_key());
+    assert_eq!(server.handle(&mut session, &sign_request(&key, &data)), [5]);
+    let data = userauth_request(b"other", "git", &key, &host.public_key());
+    assert_eq!(server.handle(&mut session, &sign_request(&key, &data)), [5]);
+    assert_eq!(server.handle(&mut session, &sign_request(&key, b"raw")), [5]);
+    // session used for authentication can't be rebound
+    assert_eq!(server.handle(&mut session, &session_bind(&host, b"x", true)),
+               vec![5]);
+
+    // forwarded beyond the permitted host
+    let mut session = Session::new();
+    assert_eq!(server.handle(&mut session, &session_bind(&host, b"sid", true)),
+               vec![6]);
+    assert_eq!(count_identities(&server, &mut session), 0);
+
+    // invalid session bind signature
+    let mut session = Session::new();
+    let mut bind = session_bind(&host, b"sid", false);
+    let len = bind.len();
+    bind[len - 2] ^= 1;
+    assert_eq!(server.handle(&mut session, &bind), vec![5]);
+    assert_eq!(count_identities(&server, &mut session), 0);
+}
+
+/// Stream that records requests and returns end of stream
+struct Capture(Vec<u8>);
+
+impl Read for Capture {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Ok(0)
+    }
+}
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.extend(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct TempDir(PathBuf);
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.0).ok();
+    }
+}
+
+#[test]
+fn ssh_add() {
+    let dir = TempDir(env::temp_dir().join(
+        format!("ssh-keys-agent-server-{}", std::process::id())));
+    fs::create_dir_all(&dir.0).unwrap();
+    let sock = dir.0.join("agent.sock");
+    let listener = UnixListener::bind(&sock).unwrap();
+    let server = Arc::new(Server::new(MemoryStore::new()));
+    thread::spawn(move || server.serve_unix(listener).unwrap());
+
+    let ssh_add = |args: &[&str]| {
+        Command::new("ssh-add")
+            .args(args)
+            .env("SSH_AUTH_SOCK", &sock)
+            .env_remove("DISPLAY")
+            .env_remove("SSH_ASKPASS")
+            .output()
+    };
+    if ssh_add(&["-l"]).is_err() {
+        return;  // no ssh-add installed, skip
+    }
+    let mut keys = Vec::new();
+    for name in &["ed25519", "rsa2048", "ecdsa521"] {
+        let path = dir.0.join(name);
+        fs::copy(format!("test-keys/{}", name), &path).unwrap();
+        fs::copy(format!("test-keys/{}.pub", name), path.with_extension("pub"))
+            .unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))
+            .unwrap();
+        keys.push(path.to_str().unwrap().to_string());
+    }
+    let out = ssh_add(&["-t", "600", &keys[0], &keys[1]]).unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let known_hosts = dir.0.join("known_hosts");
+    fs::write(&known_hosts, format!("example.com {}",
+                                    read_file("test-keys/ecdsa256.pub")))
+        .unwrap();
+    let out = ssh_add(&["-H", known_hosts.to_str().unwrap(),
+                        "-h", "git@example.com", &keys[2]]).unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    let out = ssh_add(&["-L"]).unwrap();
+    assert!(out.status.success());
+    let listed = String::from_utf8(out.stdout).unwrap();
+    let listed = listed.lines()
+        .map(|line| ssh_keys::openssh::parse_public_key(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(listed, vec![
+        public_key("test-keys/ed25519.pub"),
+        public_key("test-keys/rsa2048.pub"),
+        public_key("test-keys/ecdsa521.pub"),
+    ]);
+
+    let out = ssh_add(&["-d", &keys[1]]).unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let out = ssh_add(&["-D"]).unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let out = ssh_add(&["-l"]).unwrap();
+    assert!(!out.status.success());  // agent has no identities
+}
@@ -0,0 +1,140 @@
+use std::io::{Read};
+use std::path::Path;
+use std::fs::File;
+
+extern crate ssh_keys;
+
+use ssh_keys::PublicKey;
+use ssh_keys::allowed_signers::{AllowedSigners, Entry};
+
+
+fn read_file<T: AsRef<Path>>(path: T) -> String {
+    let mut f = File::open(path).unwrap();
+    let mut buf = String::with_capacity(128);
+    f.read_to_string(&mut buf).unwrap();
+    buf
+}
+
+fn public_key<T: AsRef<Path>>(path: T) -> PublicKey {
+    ssh_keys::openssh::parse_public_key(&read_file(path)).unwrap()
+}
+
+fn signers() -> AllowedSigners {
+    AllowedSigners::parse_with_utc_offset(&format!("\
+        # comment line\n\
+        \n\
+        alice@example.com,*@admin.example.com {ed}\n\
+        bob@example.com namespaces=\"git,file\",valid-after=\"20220101\",\
+            valid-before=\"202201021030Z\" {rsa}\n\
+        \"*@example.com,!mallory@example.com\" cert-authority {ecdsa}\n\
+        sk@example.com sk-ssh-ed25519@openssh.com AAAAGnNrLXNzaC1lZDI1NTE5QG9w\
+            ZW5zc2guY29tAAAAIDYP fido\n\
+        ",
+        ed=read_file("test-keys/ed25519.pub").trim(),
+        rsa=read_file("test-keys/rsa2048.pub").trim(),
+        ecdsa=read_file("test-keys/ecdsa256.pub").trim(),
+    ), 0).unwrap()
+}
+
+#[test]
+fn parse() {
+    let signers = signers();
+    let entries = signers.entries();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].principals,
+               vec!["alice@example.com", "*@admin.example.com"]);
+    assert_eq!(entries[0].namespaces, None);
+    assert_eq!(entries[0].key, public_key("test-keys/ed25519.pub"));
+    assert_eq!(entries[1].namespaces,
+               Some(vec!["git".to_string(), "file".to_string()]));
+    assert_eq!(entries[1].valid_after, Some(1640995200));
+    assert_eq!(entries[1].valid_before, Some(1641119400));
+    assert!(!entries[1].cert_authority);
+    assert_eq!(entries[2].principals,
+               vec!["*@example.com", "!mallory@example.com"]);
+    assert!(entries[2].cert_authority);
+}
+
+#[test]
+fn find_principals() {
+    let signers = signers();
+    let ed = public_key("test-keys/ed25519.pub");
+    assert_eq!(signers.find_principals(&ed, 0),
+               vec!["alice@example.com", "*@admin.example.com"]);
+    let rsa = public_key("test-keys/rsa2048.pub");
+    assert_eq!(signers.find_principals(&rsa, 1641000000),
+               vec!["bob@example.com"]);
+    assert!(signers.find_principals(&rsa, 1640995199).is_empty());
+    assert!(signers.find_principals(&rsa, 1641119401).is_empty());
+    // certificate authority keys are not signing keys
+    let ecdsa = public_key("test-keys/ecdsa256.pub");
+    assert!(signers.find_principals(&ecdsa, 0).is_empty());
+}
+
+#[test]
+fn is_allowed() {
+    let signers = signers();
+    let ed = public_key("test-keys/ed25519.pub");
+    let rsa = public_key("test-keys/rsa2048.pub");
+    assert!(signers.is_allowed("alice@example.com", &ed, "git", 0));
+    assert!(signers.is_allowed("root@admin.example.com", &ed, "file", 0));
+    assert!(!signers.is_allowed("bob@example.com", &ed, "git", 0));
+    assert!(signers.is_allowed("bob@example.com", &rsa, "git", 1641000000));
+    assert!(!signers.is_allowed("bob@example.com", &rsa, "email",
+                                1641000000));
+    assert!(!signers.is_allowed("bob@example.com", &rsa, "git", 0));
+    assert!(!signers.is_allowed("alice@example.com", &rsa, "git",
+                                1641000000));
+}
+
+#[test]
+fn negated_pattern() {
+    let key = read_file("test-keys/ed25519.pub");
+    let entry = Entry::parse(
+        &format!("*@example.com,!mallory@example.com {}", key)).unwrap();
+    let signers = AllowedSigners::parse(
+        &format!("*@example.com,!mallory@example.com {}", key)).unwrap();
+    assert_eq!(signers.entries(), &[entry]);
+    let key = public_key("test-keys/ed25519.pub");
+    assert!(signers.is_allowed("alice@example.com", &key, "git", 0));
+    assert!(!signers.is_allowed("mallory@example.com", &key, "git", 0));
+    assert!(!signers.is_allowed("alice@example.org", &key, "git", 0));
+}
+
+#[test]
+fn invalid() {
+    let key = read_file("test-keys/ed25519.pub");
+    Entry::parse(&format!("a@b unknown-option {}", key)).unwrap_err();
+    Entry::parse(&format!("a@b valid-after=\"2022\" {}", key)).unwrap_err();
+    Entry::parse(&format!("a@b namespaces=\"git {}", key)).unwrap_err();
+    Entry::parse("a@b").unwrap_err();
+    AllowedSigners::parse(&format!("\"a@b {}", key)).unwrap_err();
+}
+
+#[test]
+fn utc_offset() {
+    let key = read_file("test-keys/ed25519.pub");
+    let line = format!("a@b valid-after=\"202201010200\",\
+                        valid-before=\"202201010200Z\" {}", key);
+    // UTC+2
+    let entry = Entry::parse_with_utc_offset(&line, 7200).unwrap();
+    assert_eq!(entry.valid_after, Some(1640995200));
+    assert_eq!(entry.valid_before, Some(1641002400));
+    // UTC-5
+    let entry = Entry::parse_with_utc_offset(&line, -18000).unwrap();
+    assert_eq!(entry.valid_after, Some(1641020400));
+    assert_eq!(entry.valid_before, Some(1641002400));
+}
+
+#[test]
+fn pathological_pattern() {
+    let key = read_file("test-keys/ed25519.pub");
+    let pattern = format!("{}b", "*a".repeat(30));
+    let signers = AllowedSigners::parse(&format!("{},!*a*c {}",
+                                                 pattern, key)).unwrap();
+    let key = public_key("test-keys/ed25519.pub");
+    let value = "a".repeat(100);
+    assert!(!signers.is_allowed(&value, &key, "git", 0));
+    assert!(signers.is_allowed(&format!("{}b", value), &key, "git", 0));
+    assert!(!signers.is_allowed(&format!("{}cb", value), &key, "git", 0));
+}
@@ -0,0 +1,117 @@
+#![cfg(feature="std")]
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+extern crate ssh_keys;
+
+use ssh_keys::{PublicKey, PrivateKey, Error};
+use ssh_keys::files::scan_ssh_dir;
+
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir()
+        .join(format!("ssh-keys-test-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[cfg(unix)]
+fn set_mode(path: &PathBuf, mode: u32) {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+}
+
+#[test]
+fn from_file() {
+    let public = PublicKey::from_file("test-keys/ed25519.pub").unwrap();
+    match PublicKey::from_file("test-keys/nonexistent.pub").unwrap_err() {
+        Error::Io(..) => {}
+        e => panic!("wrong error {:?}", e),
+    }
+    match PrivateKey::from_file("test-keys/nonexistent").unwrap_err() {
+        Error::Io(..) => {}
+        e => panic!("wrong error {:?}", e),
+    }
+    let dir = temp_dir("from-file");
+    let path = dir.join("id_ed25519");
+    fs::copy("test-keys/ed25519", &path).unwrap();
+    #[cfg(unix)]
+    set_mode(&path, 0o600);
+    let private = PrivateKey::from_file(&path).unwrap();
+    assert_eq!(private.len(), 1);
+    assert_eq!(private[0].public_key(), public);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn from_file_insecure() {
+    let dir = temp_dir("from-file-insecure");
+    let path = dir.join("id_ed25519");
+    fs::copy("test-keys/ed25519", &path).unwrap();
+    set_mode(&path, 0o640);
+    match PrivateKey::from_file(&path).unwrap_err() {
+        Error::InsecurePermissions => {}
+        e => panic!("wrong error {:?}", e),
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn scan() {
+    let dir = temp_dir("scan");
+    fs::copy("test-keys/ed25519", dir.join("id_ed25519")).unwrap();
+    fs::copy("test-keys/ed25519.pub", dir.join("id_ed25519.pub")).unwrap();
+    fs::write(dir.join("id_ed25519-cert.pub"), "cert").unwrap();
+    fs::copy("test-keys/rsa1024", dir.join("id_rsa")).unwrap();
+    fs::copy("test-keys/ecdsa256.pub", dir.join("github.pub")).unwrap();
+    fs::copy("test-keys/ed25519-encrypted", dir.join("id_encrypted"))
+        .unwrap();
+    fs::write(dir.join("known_hosts"), "").unwrap();
+    fs::create_dir(dir.join("id_dir")).unwrap();
+    set_mode(&dir.join("id_ed25519"), 0o600);
+    set_mode(&dir.join("id_encrypted"), 0o600);
+    set_mode(&dir.join("id_rsa"), 0o644);
+
+    let keys = scan_ssh_dir(&dir).unwrap();
+    let names = keys.iter().map(|k| &k.name[..]).collect::<Vec<_>>();
+    assert_eq!(names, ["github", "id_ed25519", "id_encrypted", "id_rsa"]);
+
+    assert_eq!(keys[0].private_key, None);
+    assert_eq!(keys[0].public_key().unwrap(),
+               PublicKey::from_file("test-keys/ecdsa256.pub").unwrap());
+    assert!(keys[0].private_keys().is_err());
+
+    assert_eq!(keys[1].private_key, Some(dir.join("id_ed25519")));
+    assert_eq!(keys[1].public_key, Some(dir.join("id_ed25519.pub")));
+    assert_eq!(keys[1].certificate, Some(dir.join("id_ed25519-cert.pub")));
+    assert!(!keys[1].insecure);
+    assert_eq!(keys[1].private_keys().unwrap()[0].public_key(),
+               keys[1].public_key().unwrap());
+
+    // public key is readable without a passphrase
+    assert_eq!(keys[2].public_key().unwrap(),
+        PublicKey::from_file("test-keys/ed25519-encrypted.pub").unwrap());
+    match keys[2].private_keys().unwrap_err() {
+        Error::Encrypted => {}
+        e => panic!("wrong error {:?}", e),
+    }
+
+    assert_eq!(keys[3].public_key, None);
+    assert!(keys[3].insecure);
+    match keys[3].private_keys().unwrap_err() {
+        Error::InsecurePermissions => {}
+        e => panic!("wrong error {:?}", e),
+    }
+    assert!(keys[3].public_key().is_err());
+    set_mode(&dir.join("id_rsa"), 0o400);
+    let keys = scan_ssh_dir(&dir).unwrap();
+    assert_eq!(keys[3].public_key().unwrap(),
+               PublicKey::from_file("test-keys/rsa1024.pub").unwrap());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
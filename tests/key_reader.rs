@@ -0,0 +1,104 @@
+#![cfg(feature="std")]
+
+use std::io::{Read, BufReader};
+use std::path::Path;
+use std::fs::File;
+
+extern crate base64;
+extern crate ssh_keys;
+
+use ssh_keys::Error;
+use ssh_keys::openssh::{PublicKeyReader, ParseOptions, parse_public_key};
+
+
+fn read_file<T: AsRef<Path>>(path: T) -> String {
+    let mut f = File::open(path).unwrap();
+    let mut buf = String::with_capacity(128);
+    f.read_to_string(&mut buf).unwrap();
+    buf
+}
+
+#[test]
+fn concatenated() {
+    let names = ["rsa1024", "rsa2048", "rsa4096", "ed25519",
+                 "ecdsa256", "ecdsa384", "ecdsa521"];
+    let mut data = String::new();
+    for name in &names {
+        data.push_str(&read_file(format!("test-keys/{}.pub", name)));
+    }
+    let keys = PublicKeyReader::new(data.as_bytes())
+        .collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(keys.len(), names.len());
+    for (i, &(line_no, ref key, ref comment)) in keys.iter().enumerate() {
+        let line = read_file(format!("test-keys/{}.pub", names[i]));
+        assert_eq!(line_no, i + 1);
+        assert_eq!(*key, parse_public_key(&line).unwrap());
+        assert_eq!(comment, line.split_whitespace().nth(2).unwrap());
+    }
+}
+
+#[test]
+fn file() {
+    let file = BufReader::new(File::open("test-keys/shared-factors.pub")
+                              .unwrap());
+    assert_eq!(PublicKeyReader::new(file).count(), 6);
+}
+
+#[test]
+fn authorized_keys() {
+    let ed25519 = read_file("test-keys/ed25519.pub");
+    let ecdsa = read_file("test-keys/ecdsa256.pub");
+    let mut data = Vec::new();
+    data.extend(b"# comment\n\n");
+    data.extend(ed25519.as_bytes());
+    data.extend(b"ssh-rsa AAAA!garbage\n");
+    data.extend(b"no-pty,command=\"echo \\\"hello world\\\"\" ");
+    data.extend(ecdsa.as_bytes());
+    data.extend(b"ssh-dss AAAAB3NzaC1kc3M= dsa\n");
+    data.extend(b"\xff\xfe\n");
+    data.extend(b"   \r\n");
+    data.extend(ed25519.trim().as_bytes());  // no newline at the end
+
+    let mut reader = PublicKeyReader::new(&data[..]);
+    let (line_no, key, comment) = reader.next().unwrap().unwrap();
+    assert_eq!(line_no, 3);
+    assert_eq!(key, parse_public_key(&ed25519).unwrap());
+    assert_eq!(comment, "pc@dish");
+    match reader.next().unwrap().unwrap_err() {
+        Error::Unexpected { .. } => {}
+        e => panic!("wrong error {:?}", e),
+    }
+    assert_eq!(reader.line_number(), 4);
+    let (line_no, key, _) = reader.next().unwrap().unwrap();
+    assert_eq!(line_no, 5);
+    assert_eq!(key, parse_public_key(&ecdsa).unwrap());
+    match reader.next().unwrap().unwrap_err() {
+        Error::UnsupportedType(ref typ) if typ == "ssh-dss" => {}
+        e => panic!("wrong error {:?}", e),
+    }
+    assert!(reader.next().unwrap().is_err());
+    assert_eq!(reader.line_number(), 7);
+    let (line_no, _, comment) = reader.next().unwrap().unwrap();
+    assert_eq!(line_no, 9);
+    assert_eq!(comment, "pc@dish");
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn strict() {
+    let key = parse_public_key(&read_file("test-keys/ed25519.pub")).unwrap();
+    let mut blob = key.to_wire_blob();
+    blob.push(0);
+    let data = format!("{}\nssh-ed25519 {}\n", key, base64::encode(&blob));
+    assert_eq!(PublicKeyReader::new(data.as_bytes())
+               .filter(|x| x.is_ok()).count(), 2);
+    let mut options = ParseOptions::new();
+    options.strict(true);
+    let mut reader = PublicKeyReader::with_options(data.as_bytes(), &options);
+    assert!(reader.next().unwrap().is_ok());
+    match reader.next().unwrap().unwrap_err() {
+        Error::TrailingData { .. } => {}
+        e => panic!("wrong error {:?}", e),
+    }
+    assert!(reader.next().is_none());
+}
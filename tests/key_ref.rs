@@ -0,0 +1,82 @@
+use std::io::{Read};
+use std::path::Path;
+use std::fs::File;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+extern crate ssh_keys;
+
+use ssh_keys::{PublicKey, PublicKeyRef, EcdsaCurve};
+use ssh_keys::openssh::{parse_public_key, parse_public_key_ref};
+
+
+fn read_file<T: AsRef<Path>>(path: T) -> String {
+    let mut f = File::open(path).unwrap();
+    let mut buf = String::with_capacity(128);
+    f.read_to_string(&mut buf).unwrap();
+    buf
+}
+
+fn hash<T: Hash>(value: T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn same_as_owned() {
+    let mut buf = Vec::new();
+    for name in &["rsa1024", "rsa2048", "rsa4096", "ed25519",
+                  "ecdsa256", "ecdsa384", "ecdsa521"]
+    {
+        let line = read_file(format!("test-keys/{}.pub", name));
+        let key = parse_public_key(&line).unwrap();
+        let key_ref = parse_public_key_ref(&line, &mut buf).unwrap();
+        assert_eq!(key_ref, key);
+        assert_eq!(key_ref, key.as_key_ref());
+        assert_eq!(key_ref.to_public_key(), key);
+        assert_eq!(PublicKey::from(key_ref), key);
+        assert_eq!(key_ref.fingerprint(), key.fingerprint());
+        assert_eq!(key_ref.fingerprint_sha1(), key.fingerprint_sha1());
+        assert_eq!(key_ref.bits(), key.bits());
+        assert_eq!(key_ref.to_wire_blob(), key.to_wire_blob());
+        assert_eq!(hash(key_ref), hash(&key));
+    }
+}
+
+#[test]
+fn borrows_buffer() {
+    let line = read_file("test-keys/ecdsa256.pub");
+    let mut buf = Vec::new();
+    let key = parse_public_key_ref(&line, &mut buf).unwrap();
+    match key {
+        PublicKeyRef::Ecdsa { curve: EcdsaCurve::NistP256, point } => {
+            assert_eq!(point.len(), 65);
+            assert_eq!(key.key_type(), "ecdsa-sha2-nistp256");
+        }
+        _ => panic!("wrong key {:?}", key),
+    }
+    let blob = key.to_wire_blob();
+    assert_eq!(blob, buf);
+    let from_blob = PublicKeyRef::from_wire_blob(&blob).unwrap();
+    assert_eq!(from_blob.to_public_key(),
+               parse_public_key(&line).unwrap());
+    assert!(PublicKeyRef::from_wire_blob(&blob[..blob.len()-1]).is_err());
+}
+
+#[test]
+fn not_equal() {
+    let rsa = parse_public_key(&read_file("test-keys/rsa1024.pub")).unwrap();
+    let ed = parse_public_key(&read_file("test-keys/ed25519.pub")).unwrap();
+    assert!(rsa.as_key_ref() != ed);
+    assert!(ed.as_key_ref() != rsa);
+    assert!(ed.as_key_ref() == ed);
+}
+
+#[test]
+fn debug() {
+    let key = parse_public_key(&read_file("test-keys/ed25519.pub")).unwrap();
+    assert_eq!(format!("{:?}", key.as_key_ref()),
+        "PublicKeyRef::Ed25519(256 bits, \
+         SHA256:HIHlI25MXooP0gHXUQ4xCuieEVtlmrtpbWGzwVXoYI4)");
+}
@@ -0,0 +1,192 @@
+#![cfg(feature="std")]
+
+use std::io::{Read};
+use std::path::Path;
+use std::fs::File;
+
+extern crate ssh_keys;
+
+use ssh_keys::{PublicKey, Fingerprint};
+use ssh_keys::krl::{Krl, KrlBuilder};
+
+
+fn read_file<T: AsRef<Path>>(path: T) -> String {
+    let mut f = File::open(path).unwrap();
+    let mut buf = String::with_capacity(128);
+    f.read_to_string(&mut buf).unwrap();
+    buf
+}
+
+fn read_binary<T: AsRef<Path>>(path: T) -> Vec<u8> {
+    let mut f = File::open(path).unwrap();
+    let mut buf = Vec::with_capacity(1024);
+    f.read_to_end(&mut buf).unwrap();
+    buf
+}
+
+fn public_key<T: AsRef<Path>>(path: T) -> PublicKey {
+    ssh_keys::openssh::parse_public_key(&read_file(path)).unwrap()
+}
+
+#[test]
+fn parse_ssh_keygen() {
+    let krl = Krl::parse(&read_binary("test-keys/revoked.krl")).unwrap();
+    assert_eq!(krl.version(), 7);
+    assert_eq!(krl.comment(), "");
+    assert_eq!(krl.signing_keys(), &[]);
+    assert_eq!(krl.revoked_keys(), &[public_key("test-keys/rsa2048.pub")]);
+    // hashes are sorted by ssh-keygen
+    assert_eq!(krl.revoked_fingerprints(), &[
+        public_key("test-keys/rsa1024.pub").fingerprint_sha1(),
+        Fingerprint::parse("SHA256:HIHlI25MXooP0gHXUQ4xCuieEVtlmrtpbWGzwVXoYI4")
+            .unwrap(),
+        public_key("test-keys/ecdsa521.pub").fingerprint(),
+    ]);
+    let certs = krl.certificates();
+    assert_eq!(certs.len(), 1);
+    assert_eq!(certs[0].ca, Some(public_key("test-keys/ecdsa384.pub")));
+    assert_eq!(certs[0].serials, vec![
+        (1, 10), (100, 100), (1000, 1000), (1002, 1003), (1005, 1005),
+        (1010, 1010), (1012, 1012), (5000, 100000),
+    ]);
+    assert_eq!(certs[0].key_ids, vec!["leaked-key", "old laptop"]);
+}
+
+#[test]
+fn revoked_keys() {
+    let krl = Krl::parse(&read_binary("test-keys/revoked.krl")).unwrap();
+    // same as `ssh-keygen -Q`
+    for name in &["ed25519", "rsa1024", "rsa2048", "ecdsa521"] {
+        let key = public_key(format!("test-keys/{}.pub", name));
+        assert!(krl.is_revoked(&key), "{} must be revoked", name);
+    }
+    for name in &["rsa4096", "ecdsa256", "ecdsa384"] {
+        let key = public_key(format!("test-keys/{}.pub", name));
+        assert!(!krl.is_revoked(&key), "{} must not be revoked", name);
+    }
+}
+
+#[test]
+fn revoked_certificates() {
+    let krl = Krl::parse(&read_binary("test-keys/revoked.krl")).unwrap();
+    let ca = public_key("test-keys/ecdsa384.pub");
+    let other_ca = public_key("test-keys/rsa4096.pub");
+    let key = public_key("test-keys/ecdsa256.pub");
+    for &serial in &[1, 10, 100, 1003, 1012, 5000, 77777, 100000] {
+        assert!(krl.is_certificate_revoked(&key, &ca, serial, "id"),
+                "serial {} must be revoked", serial);
+        assert!(!krl.is_certificate_revoked(&key, &other_ca, serial, "id"));
+    }
+    for &serial in &[0, 11, 1001, 1004, 1011, 4999, 100001] {
+        assert!(!krl.is_certificate_revoked(&key, &ca, serial, "id"),
+                "serial {} must not be revoked", serial);
+    }
+    assert!(krl.is_certificate_revoked(&key, &ca, 0, "old laptop"));
+    assert!(!krl.is_certificate_revoked(&key, &other_ca, 0, "old laptop"));
+    // certificate of the revoked key
+    let revoked = public_key("test-keys/ed25519.pub");
+    assert!(krl.is_certificate_revoked(&revoked, &other_ca, 0, "id"));
+    // certificate signed by the revoked key
+    assert!(krl.is_certificate_revoked(&key, &revoked, 0, "id"));
+}
+
+#[test]
+fn invalid() {
+    let data = read_binary("test-keys/revoked.krl");
+    Krl::parse(&data[..data.len()-1]).unwrap_err();
+    Krl::parse(&data[1..]).unwrap_err();
+    let mut data = data.clone();
+    data[11] = 2;  // format version
+    Krl::parse(&data).unwrap_err();
+}
+
+#[test]
+fn build_same_as_ssh_keygen() {
+    let original = read_binary("test-keys/revoked.krl");
+    let krl = Krl::parse(&original).unwrap();
+    let ca = public_key("test-keys/ecdsa384.pub");
+    let data = KrlBuilder::new()
+        .version(7)
+        .generated_date(krl.generated_date())
+        .add_spec(&read_file("test-keys/revoked.spec"), Some(&ca)).unwrap()
+        .build();
+    assert_eq!(data, original);
+}
+
+#[test]
+fn build_api() {
+    let ca = public_key("test-keys/ecdsa384.pub");
+    let key = public_key("test-keys/ed25519.pub");
+    let data = KrlBuilder::new()
+        .comment("hello")
+        .revoke_key(&key)
+        .revoke_serial(Some(&ca), 7).unwrap()
+        .revoke_serial_range(Some(&ca), 8, 20).unwrap()
+        .revoke_serial_range(None, 100, 200).unwrap()
+        .revoke_key_id(None, "bad")
+        .build();
+    let krl = Krl::parse(&data).unwrap();
+    assert_eq!(krl.comment(), "hello");
+    assert_eq!(krl.revoked_keys().len(), 1);
+    assert!(krl.is_revoked(&key));
+    assert_eq!(krl.certificates().len(), 2);
+    assert_eq!(krl.certificates()[0].serials, vec![(7, 20)]);
+    assert_eq!(krl.certificates()[1].key_ids, vec!["bad".to_string()]);
+    assert!(krl.is_certificate_revoked(&key, &ca, 150, "x"));
+    assert!(krl.is_certificate_revoked(&key, &ca, 10, "x"));
+    let other = public_key("test-keys/rsa1024.pub");
+    assert!(!krl.is_certificate_revoked(&other, &ca, 21, "x"));
+    assert!(krl.is_certificate_revoked(&other, &ca, 21, "bad"));
+    assert!(KrlBuilder::new().revoke_serial(None, 0).is_err());
+    assert!(KrlBuilder::new().revoke_serial_range(None, 5, 4).is_err());
+}
+
+#[test]
+fn build_signed() {
+    let key = ssh_keys::openssh::parse_private_key(
+        &read_file("test-keys/ed25519")).unwrap().remove(0);
+    let mut data = KrlBuilder::new()
+        .revoke_key(&public_key("test-keys/rsa1024.pub"))
+        .build_signed(&key).unwrap();
+    let krl = Krl::parse(&data).unwrap();
+    assert_eq!(krl.signing_keys(), &[key.public_key()]);
+    let len = data.len();
+    data[len - 1] ^= 1;
+    assert!(Krl::parse(&data).is_err());
+}
+
+#[test]
+fn invalid_spec() {
+    for line in &["serial: 0", "serial: 5-5", "serial: 10-2", "serial: x",
+                  "serial: +5", "hash: SHA1:3Sl1DjDmHFsqQ4ue3o8GEUjiNHg",
+                  "key: garbage", "something"]
+    {
+        assert!(KrlBuilder::new().add_spec(line, None).is_err(),
+                "{:?} accepted", line);
+    }
+    let data = KrlBuilder::new()
+        .add_spec("  # comment\n\nSERIAL: 0x10\nserial: 010\n", None)
+        .unwrap()
+        .build();
+    let krl = Krl::parse(&data).unwrap();
+    assert_eq!(krl.certificates()[0].serials, vec![(8, 8), (16, 16)]);
+    let data = KrlBuilder::new()
+        .add_spec("serial: 5 # five", None).unwrap()
+        .build();
+    let krl = Krl::parse(&data).unwrap();
+    assert_eq!(krl.certificates()[0].serials, vec![(5, 5)]);
+}
+
+#[test]
+fn spec_key_id_with_hash() {
+    let ca = public_key("test-keys/ecdsa384.pub");
+    let key = public_key("test-keys/ed25519.pub");
+    let data = KrlBuilder::new()
+        .add_spec("# laptop keys\nid: alice#laptop\n", None).unwrap()
+        .build();
+    let krl = Krl::parse(&data).unwrap();
+    // same as ssh-keygen, the rest of the line is a comment
+    assert_eq!(krl.certificates()[0].key_ids, vec!["alice".to_string()]);
+    assert!(krl.is_certificate_revoked(&key, &ca, 1, "alice"));
+    assert!(!krl.is_certificate_revoked(&key, &ca, 1, "alice#laptop"));
+}
@@ -0,0 +1,62 @@
+use std::io::{Read};
+use std::path::Path;
+use std::fs::File;
+
+extern crate ssh_keys;
+
+use ssh_keys::{PrivateKey, PublicKey, Fingerprint, pair_keys};
+use ssh_keys::openssh::{parse_private_key, parse_public_key};
+
+
+fn read_file<T: AsRef<Path>>(path: T) -> String {
+    let mut f = File::open(path).unwrap();
+    let mut buf = String::with_capacity(128);
+    f.read_to_string(&mut buf).unwrap();
+    buf
+}
+
+fn private_key(name: &str) -> PrivateKey {
+    parse_private_key(&read_file(format!("test-keys/{}", name)))
+        .unwrap().pop().unwrap()
+}
+
+fn public_key(name: &str) -> PublicKey {
+    parse_public_key(&read_file(format!("test-keys/{}.pub", name))).unwrap()
+}
+
+#[test]
+fn matches() {
+    for name in &["rsa1024", "rsa2048", "ed25519", "ecdsa256", "ecdsa521"] {
+        let key = private_key(name);
+        assert!(key.matches(&public_key(name)), "{}", name);
+        assert_eq!(key.public_key_ref(), key.public_key().as_key_ref());
+    }
+    assert!(!private_key("rsa1024").matches(&public_key("rsa2048")));
+    assert!(!private_key("ed25519").matches(&public_key("ecdsa256")));
+    assert!(!private_key("ecdsa256").matches(&public_key("ecdsa384")));
+}
+
+#[test]
+fn pair_public_keys() {
+    let private = vec![private_key("ed25519"), private_key("rsa1024"),
+                       private_key("ecdsa256")];
+    let public = vec![public_key("rsa2048"), public_key("ecdsa256"),
+                      public_key("ed25519"), public_key("ed25519")];
+    assert_eq!(pair_keys(&private, &public), [(0, 2), (0, 3), (2, 1)]);
+    let refs = public.iter().map(|k| k.as_key_ref()).collect::<Vec<_>>();
+    assert_eq!(pair_keys(&private, &refs), [(0, 2), (0, 3), (2, 1)]);
+    assert_eq!(pair_keys::<PublicKey>(&private, &[]), []);
+}
+
+#[test]
+fn pair_fingerprints() {
+    let private = vec![private_key("rsa2048"), private_key("ecdsa384")];
+    let fingerprints = vec![
+        public_key("ecdsa384").fingerprint_sha1(),
+        public_key("rsa1024").fingerprint(),
+        public_key("rsa2048").fingerprint(),
+        Fingerprint::parse(&public_key("ecdsa384").fingerprint().to_string())
+            .unwrap(),
+    ];
+    assert_eq!(pair_keys(&private, &fingerprints), [(0, 2), (1, 0), (1, 3)]);
+}
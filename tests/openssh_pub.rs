@@ -4,7 +4,7 @@ use std::fs::File;
 
 extern crate ssh_keys;
 
-use ssh_keys::PublicKey;
+use ssh_keys::{PublicKey, EcdsaCurve, Fingerprint};
 
 
 fn read_file<T: AsRef<Path>>(path: T) -> String {
@@ -139,3 +139,148 @@ fn ed25519_display() {
          AAAAC3NzaC1lZDI1NTE5AAAAILaH/l6oa9qIRQp\
          METTMKnfavLYq8++HJVcdXY+PE2Uq");
 }
+
+#[test]
+fn ecdsa_display() {
+    for file in &["test-keys/ecdsa256.pub", "test-keys/ecdsa384.pub",
+                  "test-keys/ecdsa521.pub"]
+    {
+        let data = read_file(file);
+        let key = ssh_keys::openssh::parse_public_key(&data).unwrap();
+        let value = key.to_string() + " ";
+        assert!(data.starts_with(&value));
+    }
+}
+
+#[test]
+fn ecdsa256() {
+    let key = ssh_keys::openssh::parse_public_key(
+            &read_file("test-keys/ecdsa256.pub")
+        ).unwrap();
+    match key {
+        PublicKey::Ecdsa { curve: EcdsaCurve::NistP256, ref point } => {
+            assert_eq!(point.len(), 65);
+            assert_eq!(point[0], 4);
+        }
+        _ => panic!("wrong key {:?}", key),
+    }
+}
+
+#[test]
+fn fingerprint() {
+    let key = ssh_keys::openssh::parse_public_key(&read_file("test-keys/ed25519.pub")).unwrap();
+    assert_eq!(key.fingerprint().to_string(),
+               "SHA256:HIHlI25MXooP0gHXUQ4xCuieEVtlmrtpbWGzwVXoYI4");
+    assert_eq!(key.fingerprint_sha1().to_string(),
+               "SHA1:sBbXX6UrRSPSKs4ARdRK93VF51M");
+    let key = ssh_keys::openssh::parse_public_key(&read_file("test-keys/rsa2048.pub")).unwrap();
+    assert_eq!(key.fingerprint().to_string(),
+               "SHA256:gFvR/qupslYYmUMV8P87Bm5IopQWZliwocnwqD+42s4");
+    let key = ssh_keys::openssh::parse_public_key(&read_file("test-keys/ecdsa256.pub")).unwrap();
+    assert_eq!(key.fingerprint_sha1().to_string(),
+               "SHA1:+ISP58ETh+Xtj0a09w29AiJHS90");
+}
+
+#[test]
+fn parse_fingerprint() {
+    let key = ssh_keys::openssh::parse_public_key(&read_file("test-keys/ed25519.pub")).unwrap();
+    for value in &["SHA256:HIHlI25MXooP0gHXUQ4xCuieEVtlmrtpbWGzwVXoYI4",
+                   "SHA256:HIHlI25MXooP0gHXUQ4xCuieEVtlmrtpbWGzwVXoYI4="]
+    {
+        assert_eq!(Fingerprint::parse(value).unwrap(), key.fingerprint());
+    }
+    assert_eq!(Fingerprint::parse("SHA1:sBbXX6UrRSPSKs4ARdRK93VF51M")
+               .unwrap(), key.fingerprint_sha1());
+    Fingerprint::parse("MD5:sBbXX6UrRSPSKs4ARdRK93VF51M").unwrap_err();
+    Fingerprint::parse("SHA256:sBbXX6UrRSPSKs4ARdRK93VF51M").unwrap_err();
+    Fingerprint::parse("SHA256:???").unwrap_err();
+}
+
+#[test]
+fn debug() {
+    let key = ssh_keys::openssh::parse_public_key(
+        &read_file("test-keys/rsa2048.pub")).unwrap();
+    assert_eq!(format!("{:?}", key), "PublicKey::Rsa(2048 bits, \
+        SHA256:gFvR/qupslYYmUMV8P87Bm5IopQWZliwocnwqD+42s4)");
+    let key = ssh_keys::openssh::parse_public_key(
+        &read_file("test-keys/ecdsa256.pub")).unwrap();
+    assert_eq!(format!("{:?}", key), "PublicKey::Ecdsa(nistp256, 256 bits, \
+        SHA256:r5vTbvlmZg3fUUSA+gkhXCI2IW8QSM+ZG0KcDo06Oec)");
+}
+
+#[test]
+fn from_str() {
+    let line = read_file("test-keys/ed25519.pub");
+    let key: PublicKey = line.parse().unwrap();
+    assert_eq!(key, ssh_keys::openssh::parse_public_key(&line).unwrap());
+    assert!("ssh-rsa".parse::<PublicKey>().is_err());
+    let fingerprint: Fingerprint =
+        "SHA256:HIHlI25MXooP0gHXUQ4xCuieEVtlmrtpbWGzwVXoYI4".parse().unwrap();
+    assert_eq!(fingerprint, key.fingerprint());
+}
+
+#[test]
+fn hash_and_order() {
+    use std::collections::{BTreeSet, HashSet};
+    let keys = ["rsa2048", "ed25519", "ecdsa521", "rsa1024", "ecdsa256",
+                "ed25519", "rsa2048"].iter()
+        .map(|name| read_file(format!("test-keys/{}.pub", name))
+                    .parse::<PublicKey>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(keys.iter().collect::<HashSet<_>>().len(), 5);
+    let sorted = keys.iter().cloned().collect::<BTreeSet<_>>()
+        .into_iter().collect::<Vec<_>>();
+    let types = sorted.iter()
+        .map(|k| k.to_string().split(' ').next().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(types, ["ecdsa-sha2-nistp256", "ecdsa-sha2-nistp521",
+                       "ssh-ed25519", "ssh-rsa", "ssh-rsa"]);
+    // shorter modulus is shorter blob
+    assert_eq!(sorted[3], keys[3]);
+    let fingerprints = keys.iter()
+        .map(|k| k.fingerprint()).collect::<BTreeSet<_>>();
+    assert_eq!(fingerprints.len(), 5);
+    assert!(keys[0].fingerprint_sha1() < keys[0].fingerprint());
+}
+
+#[test]
+fn type_mismatch() {
+    let line = read_file("test-keys/ed25519.pub");
+    let blob = line.split_whitespace().nth(1).unwrap();
+    let bad = format!("ssh-rsa {}", blob);
+    assert!(ssh_keys::openssh::parse_public_key(&bad).is_err());
+    let bad = format!("ecdsa-sha2-nistp256 {}", blob);
+    assert!(ssh_keys::openssh::parse_public_key(&bad).is_err());
+}
+
+#[test]
+fn from_wire_blob() {
+    let key = ssh_keys::openssh::parse_public_key(
+            &read_file("test-keys/ecdsa384.pub")
+        ).unwrap();
+    let blob = key.to_wire_blob();
+    assert_eq!(&blob[4..23], b"ecdsa-sha2-nistp384");
+    assert_eq!(PublicKey::from_wire_blob(&blob).unwrap(), key);
+    // curve name must match the key type
+    let mut bad = blob.clone();
+    assert_eq!(&bad[27..35], b"nistp384");
+    bad[33..35].copy_from_slice(b"56");
+    assert!(PublicKey::from_wire_blob(&bad).is_err());
+}
+
+#[test]
+fn base64_padding() {
+    let line = read_file("test-keys/rsa1024.pub");
+    let key = ssh_keys::openssh::parse_public_key(&line).unwrap();
+    let unpadded = line.replace("== ", " ");
+    assert_eq!(ssh_keys::openssh::parse_public_key(&unpadded).unwrap(), key);
+    let bad = line.replace("== ", "=== ");
+    assert!(ssh_keys::openssh::parse_public_key(&bad).is_err());
+    let bad = line.replace("== ", "=A= ");
+    match ssh_keys::openssh::parse_public_key(&bad).unwrap_err() {
+        ssh_keys::Error::Unexpected { offset, .. } => {
+            assert_eq!(offset, line.find("== ").unwrap() + 1);
+        }
+        e => panic!("wrong error {:?}", e),
+    }
+}
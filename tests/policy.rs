@@ -0,0 +1,85 @@
+use std::io::{Read};
+use std::path::Path;
+use std::fs::File;
+
+extern crate ssh_keys;
+
+use ssh_keys::{PublicKey, EcdsaCurve, Signature, SignatureAlgorithm};
+use ssh_keys::policy::{KeyPolicy, Violation};
+
+
+fn read_file<T: AsRef<Path>>(path: T) -> String {
+    let mut f = File::open(path).unwrap();
+    let mut buf = String::with_capacity(128);
+    f.read_to_string(&mut buf).unwrap();
+    buf
+}
+
+fn public_key<T: AsRef<Path>>(path: T) -> PublicKey {
+    ssh_keys::openssh::parse_public_key(&read_file(path)).unwrap()
+}
+
+#[test]
+fn bits() {
+    assert_eq!(public_key("test-keys/rsa1024.pub").bits(), 1024);
+    assert_eq!(public_key("test-keys/rsa2048.pub").bits(), 2048);
+    assert_eq!(public_key("test-keys/rsa4096.pub").bits(), 4096);
+    assert_eq!(public_key("test-keys/ed25519.pub").bits(), 256);
+    assert_eq!(public_key("test-keys/ecdsa521.pub").bits(), 521);
+}
+
+#[test]
+fn default_allows_everything() {
+    let policy = KeyPolicy::new();
+    for name in &["rsa1024", "ed25519", "ecdsa256"] {
+        let key = public_key(format!("test-keys/{}.pub", name));
+        assert_eq!(policy.check(&key), vec![]);
+    }
+    assert_eq!(policy.check_type("ssh-dss"), vec![]);
+}
+
+#[test]
+fn violations() {
+    let mut policy = KeyPolicy::new();
+    policy
+        .allowed_types(vec!["ssh-rsa", "ecdsa-sha2-nistp256",
+                            "ecdsa-sha2-nistp384"])
+        .min_rsa_bits(2048)
+        .min_ecdsa_curve(EcdsaCurve::NistP384)
+        .forbid_dsa(true)
+        .forbid_sha1_signatures(true);
+    assert_eq!(policy.check(&public_key("test-keys/rsa1024.pub")),
+               vec![Violation::RsaTooShort { bits: 1024, min_bits: 2048 }]);
+    assert_eq!(policy.check(&public_key("test-keys/rsa2048.pub")), vec![]);
+    assert_eq!(policy.check(&public_key("test-keys/ecdsa256.pub")),
+               vec![Violation::CurveTooWeak {
+                   curve: EcdsaCurve::NistP256,
+                   min_curve: EcdsaCurve::NistP384,
+               }]);
+    assert_eq!(policy.check(&public_key("test-keys/ecdsa384.pub")), vec![]);
+    assert_eq!(policy.check(&public_key("test-keys/ed25519.pub")),
+               vec![Violation::TypeNotAllowed("ssh-ed25519".into())]);
+    assert_eq!(policy.check_type("ssh-dss"),
+               vec![Violation::TypeNotAllowed("ssh-dss".into()),
+                    Violation::Dsa]);
+    assert_eq!(
+        policy.check_signature(
+            &Signature::new(SignatureAlgorithm::RsaSha1, vec![])),
+        vec![Violation::Sha1Signature]);
+    assert_eq!(
+        policy.check_signature(
+            &Signature::new(SignatureAlgorithm::RsaSha512, vec![])),
+        vec![]);
+    assert_eq!(
+        Violation::RsaTooShort { bits: 1024, min_bits: 2048 }.to_string(),
+        "RSA key has 1024 bits, at least 2048 required");
+}
+
+#[test]
+fn security_key() {
+    let mut policy = KeyPolicy::new();
+    policy.require_security_key(true);
+    assert_eq!(policy.check(&public_key("test-keys/ed25519.pub")),
+               vec![Violation::NotSecurityKey]);
+    assert_eq!(policy.check_type("sk-ssh-ed25519@openssh.com"), vec![]);
+}
@@ -0,0 +1,166 @@
+use std::io::{Read};
+use std::path::Path;
+use std::fs::File;
+
+extern crate base64;
+extern crate ssh_keys;
+
+use ssh_keys::{PrivateKey, PublicKey, Signature, SignatureAlgorithm, Error};
+
+
+fn read_file<T: AsRef<Path>>(path: T) -> String {
+    let mut f = File::open(path).unwrap();
+    let mut buf = String::with_capacity(128);
+    f.read_to_string(&mut buf).unwrap();
+    buf
+}
+
+fn public_key<T: AsRef<Path>>(path: T) -> PublicKey {
+    ssh_keys::openssh::parse_public_key(&read_file(path)).unwrap()
+}
+
+fn private_key<T: AsRef<Path>>(path: T) -> PrivateKey {
+    ssh_keys::openssh::parse_private_key(&read_file(path))
+        .unwrap().remove(0)
+}
+
+#[test]
+fn ed25519() {
+    let key = private_key("test-keys/ed25519");
+    let sig = key.sign(b"test data", SignatureAlgorithm::Ed25519).unwrap();
+    assert_eq!(sig.algorithm(), SignatureAlgorithm::Ed25519);
+    assert_eq!(sig.blob(), &base64::decode(
+        "0FbZhlAJp1vXkkMsTEvKYRycGqZFuV76Qlzi8T6GmMoH3u21hqRktsJTBQeHbhOy\
+         ClQZlLoroZSzRTw3mrolBg==").unwrap()[..]);
+    let bytes = sig.to_bytes();
+    assert_eq!(&bytes[..15], b"\0\0\0\x0bssh-ed25519");
+    assert_eq!(&bytes[15..19], b"\0\0\0\x40");
+    assert_eq!(&bytes[19..], sig.blob());
+}
+
+#[test]
+fn rsa_sha256() {
+    let key = private_key("test-keys/rsa1024");
+    let sig = key.sign(b"test data", SignatureAlgorithm::RsaSha256).unwrap();
+    assert_eq!(sig.blob(), &base64::decode(
+        "V7cLglIqYr7RYJsJyoWkZtqlosUcis8q2sLAAc2c6Kq+lkmd33Q1aY0srjvQFVUj\
+         dHJcxkMcNHF9JWNKa+qeJ//aEAbgZwoKVlfJvjCSRE8Sc/dDJ0i52GjHUrc/5PZ6\
+         5d0tzZGZyQENDTZU2R7WuWkOTE+bj7J0fN+4VqaTgVM=").unwrap()[..]);
+    assert_eq!(&sig.to_bytes()[..16], b"\0\0\0\x0crsa-sha2-256");
+}
+
+#[test]
+fn rsa_sha512() {
+    let key = private_key("test-keys/rsa1024new");
+    let sig = key.sign(b"test data", SignatureAlgorithm::RsaSha512).unwrap();
+    assert_eq!(sig.blob(), &base64::decode(
+        "tkuuNgYTsh6L7UCJ+yij28s1Q5kiEciSlJDRy2s2bCaKjWONrivpR5mvvGlzdIfV\
+         TAEJRWZqgzEhA/MLUFfE8hnpCj+OjS+bKFU8IwhAoxps4s5LWEG88SdqTJ/NiZQW\
+         E5u1O2UgvxAbDs80GBKKMzJUam7CQae/++SQZn3Z/NU=").unwrap()[..]);
+}
+
+#[test]
+fn rsa_sha1() {
+    let key = private_key("test-keys/rsa1024");
+    let sig = key.sign(b"test data", SignatureAlgorithm::RsaSha1).unwrap();
+    assert_eq!(sig.blob(), &base64::decode(
+        "EodgUKMyftqbRPm4hEMHm7aX3HDtp87mgGCn9G6dxUXgF41MrBwTC3mgKBUoBuxW\
+         Rj6RDpygJG6ppol10ETUuQjUdJgux4wYR7H9yH9EzaSk63QcdUjbE8hA2IYHLs40\
+         QVgekG+iDkWhxLmOwgu4AzjWafepgnbwjaTIJA4ierg=").unwrap()[..]);
+    assert_eq!(&sig.to_bytes()[..11], b"\0\0\0\x07ssh-rsa");
+}
+
+#[test]
+fn ecdsa() {
+    for &(file, alg) in &[
+        ("test-keys/ecdsa256", SignatureAlgorithm::EcdsaNistP256),
+        ("test-keys/ecdsa384", SignatureAlgorithm::EcdsaNistP384),
+        ("test-keys/ecdsa521", SignatureAlgorithm::EcdsaNistP521),
+    ] {
+        let key = private_key(file);
+        let sig = key.sign(b"test data", alg).unwrap();
+        assert_eq!(sig.algorithm(), alg);
+        // blob is `mpint r` followed by `mpint s`
+        let blob = sig.blob();
+        let rlen = ((blob[0] as usize) << 24) | ((blob[1] as usize) << 16) |
+                   ((blob[2] as usize) << 8) | blob[3] as usize;
+        let s = &blob[4+rlen..];
+        let slen = ((s[0] as usize) << 24) | ((s[1] as usize) << 16) |
+                   ((s[2] as usize) << 8) | s[3] as usize;
+        assert_eq!(s.len(), slen + 4);
+    }
+}
+
+#[test]
+fn incompatible() {
+    let key = private_key("test-keys/ed25519");
+    match key.sign(b"test data", SignatureAlgorithm::RsaSha256) {
+        Err(Error::IncompatibleAlgorithm("rsa-sha2-256")) => {}
+        r => panic!("unexpected result {:?}", r),
+    }
+    let key = private_key("test-keys/ecdsa256");
+    key.sign(b"test data", SignatureAlgorithm::EcdsaNistP384).unwrap_err();
+}
+
+#[test]
+fn verify_roundtrip() {
+    for &(file, alg) in &[
+        ("test-keys/ed25519", SignatureAlgorithm::Ed25519),
+        ("test-keys/rsa1024", SignatureAlgorithm::RsaSha1),
+        ("test-keys/rsa1024", SignatureAlgorithm::RsaSha256),
+        ("test-keys/rsa2048", SignatureAlgorithm::RsaSha512),
+        ("test-keys/ecdsa256", SignatureAlgorithm::EcdsaNistP256),
+        ("test-keys/ecdsa384", SignatureAlgorithm::EcdsaNistP384),
+        ("test-keys/ecdsa521", SignatureAlgorithm::EcdsaNistP521),
+    ] {
+        let key = private_key(file);
+        let public = public_key(format!("{}.pub", file));
+        let sig = key.sign(b"test data", alg).unwrap();
+        let sig = Signature::from_bytes(&sig.to_bytes()).unwrap();
+        public.verify(b"test data", &sig).unwrap();
+        match public.verify(b"other data", &sig) {
+            Err(Error::InvalidSignature) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+    }
+}
+
+#[test]
+fn verify_external_ecdsa() {
+    let public = public_key("test-keys/ecdsa384.pub");
+    let sig = Signature::from_bytes(&base64::decode(
+        "AAAAE2VjZHNhLXNoYTItbmlzdHAzODQAAABpAAAAMFgreHVSrok5LFdWwSS+3WVm\
+         jnLmcH341Ym/WaTE6Vn7KT/hRJi8hFbdqQxL35q4MQAAADEAnh2oS8O3A+nFuN0d\
+         YN1sJ26EFxJulQXyYpNYddDk7hj4fzyeQ4qkTnzqG68dxcaX").unwrap()).unwrap();
+    assert_eq!(sig.algorithm(), SignatureAlgorithm::EcdsaNistP384);
+    public.verify(b"test data", &sig).unwrap();
+    public.verify(b"test dat", &sig).unwrap_err();
+}
+
+#[test]
+fn verify_strict() {
+    let key = private_key("test-keys/rsa1024");
+    let public = key.public_key();
+    let sig = key.sign(b"test data", SignatureAlgorithm::RsaSha1).unwrap();
+    public.verify(b"test data", &sig).unwrap();
+    match public.verify_strict(b"test data", &sig) {
+        Err(Error::InsecureAlgorithm("ssh-rsa")) => {}
+        r => panic!("unexpected result {:?}", r),
+    }
+    let sig = key.sign(b"test data", SignatureAlgorithm::RsaSha256).unwrap();
+    public.verify_strict(b"test data", &sig).unwrap();
+}
+
+#[test]
+fn verify_wrong_key() {
+    let sig = private_key("test-keys/ed25519")
+        .sign(b"test data", SignatureAlgorithm::Ed25519).unwrap();
+    match public_key("test-keys/rsa1024.pub").verify(b"test data", &sig) {
+        Err(Error::IncompatibleAlgorithm("ssh-ed25519")) => {}
+        r => panic!("unexpected result {:?}", r),
+    }
+    let sig = private_key("test-keys/ecdsa256")
+        .sign(b"test data", SignatureAlgorithm::EcdsaNistP256).unwrap();
+    public_key("test-keys/ecdsa384.pub").verify(b"test data", &sig)
+        .unwrap_err();
+}
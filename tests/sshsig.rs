@@ -0,0 +1,103 @@
+use std::io::{Read};
+use std::path::Path;
+use std::fs::File;
+
+extern crate ssh_keys;
+
+use ssh_keys::{PrivateKey, PublicKey, Error};
+use ssh_keys::sshsig::{SshSig, HashAlgorithm};
+
+
+fn read_file<T: AsRef<Path>>(path: T) -> String {
+    let mut f = File::open(path).unwrap();
+    let mut buf = String::with_capacity(128);
+    f.read_to_string(&mut buf).unwrap();
+    buf
+}
+
+fn public_key<T: AsRef<Path>>(path: T) -> PublicKey {
+    ssh_keys::openssh::parse_public_key(&read_file(path)).unwrap()
+}
+
+fn private_key<T: AsRef<Path>>(path: T) -> PrivateKey {
+    ssh_keys::openssh::parse_private_key(&read_file(path))
+        .unwrap().remove(0)
+}
+
+#[test]
+fn ed25519_same_as_ssh_keygen() {
+    let message = read_file("test-keys/message.txt");
+    let sig = SshSig::sign(&private_key("test-keys/ed25519"),
+        "file", HashAlgorithm::Sha512, message.as_bytes()).unwrap();
+    assert_eq!(sig.to_armored(),
+               read_file("test-keys/message.txt.ed25519.sig"));
+}
+
+#[test]
+fn rsa_same_as_ssh_keygen() {
+    let message = read_file("test-keys/message.txt");
+    let sig = SshSig::sign(&private_key("test-keys/rsa2048"),
+        "file", HashAlgorithm::Sha512, message.as_bytes()).unwrap();
+    assert_eq!(sig.to_armored(),
+               read_file("test-keys/message.txt.rsa2048.sig"));
+}
+
+#[test]
+fn verify_ssh_keygen() {
+    let message = read_file("test-keys/message.txt");
+    for name in &["ed25519", "rsa2048", "ecdsa256"] {
+        let sig = SshSig::from_armored(&read_file(
+            format!("test-keys/message.txt.{}.sig", name))).unwrap();
+        let key = public_key(format!("test-keys/{}.pub", name));
+        assert_eq!(sig.public_key(), &key);
+        assert_eq!(sig.namespace(), "file");
+        assert_eq!(sig.hash_algorithm(), HashAlgorithm::Sha512);
+        sig.verify(&key, "file", message.as_bytes()).unwrap();
+        match sig.verify(&key, "file", b"other message") {
+            Err(Error::InvalidSignature) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+    }
+}
+
+#[test]
+fn sha256_roundtrip() {
+    let key = private_key("test-keys/ecdsa384");
+    let sig = SshSig::sign(&key, "git", HashAlgorithm::Sha256, b"commit")
+        .unwrap();
+    let sig = SshSig::from_armored(&sig.to_armored()).unwrap();
+    assert_eq!(sig.hash_algorithm(), HashAlgorithm::Sha256);
+    sig.verify(&key.public_key(), "git", b"commit").unwrap();
+}
+
+#[test]
+fn wrong_namespace() {
+    let sig = SshSig::from_armored(&read_file(
+        "test-keys/message.txt.ed25519.sig")).unwrap();
+    let message = read_file("test-keys/message.txt");
+    match sig.verify(&public_key("test-keys/ed25519.pub"),
+                     "git", message.as_bytes())
+    {
+        Err(Error::NamespaceMismatch) => {}
+        r => panic!("unexpected result {:?}", r),
+    }
+}
+
+#[test]
+fn wrong_key() {
+    let sig = SshSig::from_armored(&read_file(
+        "test-keys/message.txt.ed25519.sig")).unwrap();
+    let message = read_file("test-keys/message.txt");
+    match sig.verify(&public_key("test-keys/rsa2048.pub"),
+                     "file", message.as_bytes())
+    {
+        Err(Error::KeyMismatch) => {}
+        r => panic!("unexpected result {:?}", r),
+    }
+}
+
+#[test]
+fn invalid_armor() {
+    SshSig::from_armored("-----BEGIN SSH SIGNATURE-----").unwrap_err();
+    SshSig::from_armored(&read_file("test-keys/ed25519")).unwrap_err();
+}
@@ -0,0 +1,247 @@
+use std::io::{Read};
+use std::path::Path;
+use std::fs::File;
+
+extern crate base64;
+extern crate ssh_keys;
+
+use ssh_keys::Error;
+use ssh_keys::openssh::{ParseOptions, Section};
+use ssh_keys::openssh::{parse_public_key, parse_private_key};
+
+
+fn read_file<T: AsRef<Path>>(path: T) -> String {
+    let mut f = File::open(path).unwrap();
+    let mut buf = String::with_capacity(128);
+    f.read_to_string(&mut buf).unwrap();
+    buf
+}
+
+fn strict() -> ParseOptions {
+    let mut options = ParseOptions::new();
+    options.strict(true);
+    options
+}
+
+/// Decodes body of the PEM file and returns header, data and footer
+fn pem_decode(text: &str) -> (String, Vec<u8>, String) {
+    let lines = text.lines().collect::<Vec<_>>();
+    let data = base64::decode(&lines[1..lines.len()-1].concat()).unwrap();
+    (lines[0].to_string(), data, lines[lines.len()-1].to_string())
+}
+
+fn pem_encode(header: &str, data: &[u8], footer: &str) -> String {
+    format!("{}\n{}\n{}\n", header, base64::encode(data), footer)
+}
+
+#[test]
+fn strict_accepts_good_keys() {
+    for name in &["rsa1024", "rsa2048", "rsa4096", "ed25519",
+                  "ecdsa256", "ecdsa384", "ecdsa521"]
+    {
+        let public = read_file(format!("test-keys/{}.pub", name));
+        assert_eq!(strict().parse_public_key(&public).unwrap(),
+                   parse_public_key(&public).unwrap());
+        let private = read_file(format!("test-keys/{}", name));
+        assert_eq!(strict().parse_private_key(&private).unwrap(),
+                   parse_private_key(&private).unwrap());
+    }
+    let private = read_file("test-keys/rsa1024new");
+    strict().parse_private_key(&private).unwrap();
+}
+
+#[test]
+fn public_trailing_data() {
+    let line = read_file("test-keys/ed25519.pub");
+    let mut blob = base64::decode(line.split(' ').nth(1).unwrap()).unwrap();
+    let len = blob.len();
+    blob.extend(b"\0\0");
+    let line = format!("ssh-ed25519 {} comment", base64::encode(&blob));
+    parse_public_key(&line).unwrap();
+    match strict().parse_public_key(&line).unwrap_err() {
+        Error::TrailingData { section: Section::PublicKey, offset } => {
+            assert_eq!(offset, len);
+        }
+        e => panic!("wrong error {:?}", e),
+    }
+    strict().parse_public_blob(&blob[..len]).unwrap();
+    assert!(strict().parse_public_blob(&blob).is_err());
+}
+
+#[test]
+fn public_errors() {
+    let line = read_file("test-keys/ecdsa256.pub");
+    let data = line.split(' ').nth(1).unwrap();
+    let blob = base64::decode(data).unwrap();
+
+    let bad = format!("ecdsa-sha2-nistp256 {}", base64::encode(&blob[..50]));
+    match parse_public_key(&bad).unwrap_err() {
+        Error::Truncated { section: Section::PublicKey, offset } => {
+            // point starts after type and curve name
+            assert_eq!(offset, 4 + 19 + 4 + 8);
+        }
+        e => panic!("wrong error {:?}", e),
+    }
+
+    let bad = format!("ecdsa-sha2-nistp256 {}!AAA", &data[..12]);
+    let err = parse_public_key(&bad).unwrap_err();
+    match err {
+        Error::Unexpected { section: Section::Base64, offset, ref found,
+                            .. }
+        => {
+            assert_eq!(offset, 20 + 12);
+            assert_eq!(found, "'!'");
+        }
+        ref e => panic!("wrong error {:?}", e),
+    }
+    assert_eq!(err.to_string(), "invalid base64 data at byte 32: \
+        expected base64 character, found '!'");
+
+    let bad = format!("ssh-ed25519 {}", data);
+    match parse_public_key(&bad).unwrap_err() {
+        Error::Unexpected { section: Section::PublicKey, offset: 0,
+                            ref expected, ref found }
+        => {
+            assert_eq!(expected, "\"ssh-ed25519\"");
+            assert_eq!(found, "\"ecdsa-sha2-nistp256\"");
+        }
+        e => panic!("wrong error {:?}", e),
+    }
+}
+
+#[test]
+fn private_padding() {
+    let (header, mut data, footer) = pem_decode(
+        &read_file("test-keys/ed25519"));
+    assert_eq!(&data[data.len()-6..], b"\x01\x02\x03\x04\x05\x06");
+    let len = data.len();
+    data[len-1] = 0;
+    let key = pem_encode(&header, &data, &footer);
+    parse_private_key(&key).unwrap();
+    match strict().parse_private_key(&key).unwrap_err() {
+        Error::Unexpected { section: Section::PrivateKey, offset,
+                            ref expected, ref found }
+        => {
+            assert_eq!(offset, len-1);
+            assert_eq!(expected, "padding byte 0x06");
+            assert_eq!(found, "0x00");
+        }
+        e => panic!("wrong error {:?}", e),
+    }
+}
+
+#[test]
+fn private_check_integers() {
+    let (header, mut data, footer) = pem_decode(
+        &read_file("test-keys/ecdsa256"));
+    // magic, cipher, kdf, options, number of keys, public key
+    let pub_len = 4 + 19 + 4 + 8 + 4 + 65;
+    let offset = 15 + 8 + 8 + 4 + 4 + 4 + pub_len + 4;
+    data[offset+7] ^= 1;
+    let key = pem_encode(&header, &data, &footer);
+    match parse_private_key(&key).unwrap_err() {
+        Error::Unexpected { section: Section::PrivateKey, offset: off, .. }
+        => assert_eq!(off, offset + 4),
+        e => panic!("wrong error {:?}", e),
+    }
+}
+
+#[test]
+fn private_public_mismatch() {
+    let (header, mut data, footer) = pem_decode(
+        &read_file("test-keys/ed25519"));
+    // last byte of the public key in the header
+    let offset = 15 + 8 + 8 + 4 + 4 + 4 + 4 + 11 + 4 + 31;
+    data[offset] ^= 1;
+    let key = pem_encode(&header, &data, &footer);
+    parse_private_key(&key).unwrap();
+    match strict().parse_private_key(&key).unwrap_err() {
+        Error::Unexpected { section: Section::Header, .. } => {}
+        e => panic!("wrong error {:?}", e),
+    }
+}
+
+#[test]
+fn der_trailing_data() {
+    let text = read_file("test-keys/rsa1024");
+    let (header, mut data, footer) = pem_decode(&text);
+    let len = data.len();
+    data.extend(b"\x05\x00");
+    let key = pem_encode(&header, &data, &footer);
+    assert_eq!(parse_private_key(&key).unwrap(),
+               parse_private_key(&text).unwrap());
+    match strict().parse_private_key(&key).unwrap_err() {
+        Error::TrailingData { section: Section::Der, offset } => {
+            assert_eq!(offset, len);
+        }
+        e => panic!("wrong error {:?}", e),
+    }
+}
+
+#[test]
+fn der_wrong_tag() {
+    let (header, mut data, footer) = pem_decode(
+        &read_file("test-keys/rsa1024"));
+    data[0] = 0x31;
+    let key = pem_encode(&header, &data, &footer);
+    let err = parse_private_key(&key).unwrap_err();
+    assert_eq!(err.to_string(), "invalid DER data at byte 0: \
+        expected SEQUENCE (0x30), found 0x31");
+}
+
+#[test]
+fn missing_footer() {
+    let text = read_file("test-keys/ed25519");
+    let text = &text[..text.find("-----END").unwrap()];
+    match parse_private_key(text).unwrap_err() {
+        Error::Unexpected { section: Section::Armor, offset, .. } => {
+            assert_eq!(offset, text.len());
+        }
+        e => panic!("wrong error {:?}", e),
+    }
+}
+
+#[test]
+fn base64_padding() {
+    let text = read_file("test-keys/ecdsa256.pub");
+    let data = text.split_whitespace().nth(1).unwrap();
+    assert!(data.ends_with("=") && !data.ends_with("=="));
+    let key = parse_public_key(&text).unwrap();
+
+    // too much padding is rejected in any mode
+    let extra = format!("ecdsa-sha2-nistp256 {}==", data);
+    for result in &[parse_public_key(&extra),
+                    strict().parse_public_key(&extra)]
+    {
+        match *result {
+            Err(Error::Unexpected { section: Section::Base64, .. }) => {}
+            ref e => panic!("wrong result {:?}", e),
+        }
+    }
+
+    // missing padding is accepted in lenient mode only
+    let missing = format!("ecdsa-sha2-nistp256 {}",
+                          data.trim_end_matches('='));
+    assert_eq!(parse_public_key(&missing).unwrap(), key);
+    match strict().parse_public_key(&missing).unwrap_err() {
+        Error::Unexpected { section: Section::Base64, ref expected, .. } => {
+            assert_eq!(expected, "padding");
+        }
+        e => panic!("wrong error {:?}", e),
+    }
+
+    // unused bits of the last symbol must be zero in strict mode
+    const ALPHABET: &str =
+        "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let last = data.len() - 2;
+    let value = ALPHABET.find(&data[last..last+1]).unwrap();
+    let changed = format!("ecdsa-sha2-nistp256 {}{}=", &data[..last],
+                          &ALPHABET[value | 1..(value | 1) + 1]);
+    assert_eq!(parse_public_key(&changed).unwrap(), key);
+    match strict().parse_public_key(&changed).unwrap_err() {
+        Error::Unexpected { section: Section::Base64, ref expected, .. } => {
+            assert_eq!(expected, "zero trailing bits");
+        }
+        e => panic!("wrong error {:?}", e),
+    }
+}
@@ -0,0 +1,98 @@
+#![cfg(feature="std")]
+
+use std::io::{Read};
+use std::path::Path;
+use std::fs::File;
+
+extern crate ssh_keys;
+
+use ssh_keys::PublicKey;
+use ssh_keys::weak::{self, DebianBlocklist, Weakness};
+
+
+fn read_file<T: AsRef<Path>>(path: T) -> String {
+    let mut f = File::open(path).unwrap();
+    let mut buf = String::with_capacity(128);
+    f.read_to_string(&mut buf).unwrap();
+    buf
+}
+
+fn public_key<T: AsRef<Path>>(path: T) -> PublicKey {
+    ssh_keys::openssh::parse_public_key(&read_file(path)).unwrap()
+}
+
+#[test]
+fn good_keys() {
+    for name in &["rsa1024", "rsa2048", "rsa4096", "ed25519", "ecdsa256"] {
+        let key = public_key(format!("test-keys/{}.pub", name));
+        assert_eq!(weak::check(&key, None), vec![], "{}", name);
+    }
+}
+
+#[test]
+fn roca() {
+    let key = public_key("test-keys/roca.pub");
+    assert!(weak::is_roca(&key));
+    assert_eq!(weak::check(&key, None), vec![Weakness::Roca]);
+    assert!(!weak::is_roca(&public_key("test-keys/rsa2048.pub")));
+}
+
+#[test]
+fn small_factor() {
+    let key = public_key("test-keys/small-factor.pub");
+    assert_eq!(weak::small_factor(&key), Some(65521));
+    assert_eq!(weak::check(&key, None), vec![Weakness::SmallFactor(65521)]);
+}
+
+#[test]
+fn fermat() {
+    let key = public_key("test-keys/fermat.pub");
+    let (p, q) = weak::fermat_factor(&key).unwrap();
+    assert!(p < q);
+    assert_eq!(p.len(), 128);
+    assert_eq!(q.len(), 128);
+    match weak::check(&key, None)[..] {
+        [Weakness::Fermat { .. }] => {}
+        ref other => panic!("unexpected result {:?}", other),
+    }
+    assert_eq!(weak::fermat_factor(&public_key("test-keys/rsa2048.pub")),
+               None);
+}
+
+#[test]
+fn debian_blocklist() {
+    let list = DebianBlocklist::from_file(
+        "test-keys/debian-blocklist.RSA-1024").unwrap();
+    assert_eq!(list.len(), 4);
+    let key = public_key("test-keys/rsa1024.pub");
+    assert!(list.contains(&key));
+    assert!(!list.contains(&public_key("test-keys/rsa2048.pub")));
+    assert_eq!(weak::check(&key, Some(&list)), vec![Weakness::DebianWeakKey]);
+    assert!(DebianBlocklist::parse("xyz").is_err());
+    assert!(DebianBlocklist::parse("39cd1a4a08c8730f6a6").is_err());
+}
+
+#[test]
+fn shared_factors() {
+    let mut keys = read_file("test-keys/shared-factors.pub").lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| ssh_keys::openssh::parse_public_key(line).unwrap())
+        .collect::<Vec<_>>();
+    // duplicates and unrelated keys are ignored
+    let first = keys[0].clone();
+    keys.push(first);
+    keys.push(public_key("test-keys/ed25519.pub"));
+    keys.push(public_key("test-keys/rsa2048.pub"));
+    let shared = weak::shared_factors(&keys);
+    assert_eq!(shared.iter().map(|s| (s.first, s.second)).collect::<Vec<_>>(),
+               vec![(0, 1), (2, 3), (2, 4), (3, 4)]);
+    assert_eq!(shared[1].factor, shared[2].factor);
+    assert_ne!(shared[0].factor, shared[1].factor);
+    for item in &shared {
+        assert_eq!(item.factor.len(), 64);
+        assert_eq!(item.first_cofactor.len(), 64);
+        assert_eq!(item.second_cofactor.len(), 64);
+        assert_ne!(item.first_cofactor, item.second_cofactor);
+    }
+    assert_eq!(weak::shared_factors(&keys[5..]), vec![]);
+}
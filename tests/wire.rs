@@ -0,0 +1,129 @@
+use std::io::{Read};
+use std::path::Path;
+use std::fs::File;
+
+extern crate base64;
+extern crate ssh_keys;
+
+use ssh_keys::PublicKey;
+use ssh_keys::openssh::parse_public_key;
+use ssh_keys::wire::{Reader, Writer};
+
+
+fn read_file<T: AsRef<Path>>(path: T) -> String {
+    let mut f = File::open(path).unwrap();
+    let mut buf = String::with_capacity(128);
+    f.read_to_string(&mut buf).unwrap();
+    buf
+}
+
+#[test]
+fn roundtrip() {
+    let mut writer = Writer::new();
+    writer
+        .write_byte(7)
+        .write_bool(true)
+        .write_bool(false)
+        .write_u32(0xdeadbeef)
+        .write_u64(0x0102030405060708)
+        .write_bytes(b"\x00\xff")
+        .write_string("hello")
+        .write_mpint(&[0x12, 0x34])
+        .write_name_list(&["aes128-ctr", "aes256-ctr"])
+        .write_name_list::<&str>(&[]);
+    let data = writer.into_bytes();
+    let mut reader = Reader::new(&data);
+    assert_eq!(reader.read_byte().unwrap(), 7);
+    assert!(reader.read_bool().unwrap());
+    assert!(!reader.read_bool().unwrap());
+    assert_eq!(reader.read_u32().unwrap(), 0xdeadbeef);
+    assert_eq!(reader.read_u64().unwrap(), 0x0102030405060708);
+    assert_eq!(reader.read_bytes().unwrap(), b"\x00\xff");
+    assert_eq!(reader.read_string().unwrap(), "hello");
+    assert_eq!(reader.read_mpint().unwrap(), &[0x12, 0x34]);
+    assert_eq!(reader.read_name_list().unwrap(),
+               vec!["aes128-ctr", "aes256-ctr"]);
+    assert_eq!(reader.read_name_list().unwrap(), Vec::<&str>::new());
+    assert!(reader.is_empty());
+    assert_eq!(reader.position(), data.len());
+    assert!(reader.read_byte().is_err());
+    assert!(reader.read_u32().is_err());
+}
+
+#[test]
+fn mpint() {
+    // examples from RFC 4251
+    let mut writer = Writer::new();
+    writer.write_mpint(&[]);
+    writer.write_mpint(&[0, 0, 0x80]);
+    writer.write_mpint(&[0x09, 0xa3, 0x78, 0xf9, 0xb2, 0xe3, 0x32, 0xa7]);
+    assert_eq!(writer.as_bytes(), &[
+        0, 0, 0, 0,
+        0, 0, 0, 2, 0, 0x80,
+        0, 0, 0, 8, 0x09, 0xa3, 0x78, 0xf9, 0xb2, 0xe3, 0x32, 0xa7,
+    ][..]);
+    let mut reader = Reader::new(writer.as_bytes());
+    assert_eq!(reader.read_mpint().unwrap(), &[]);
+    assert_eq!(reader.read_mpint().unwrap(), &[0x80]);
+    assert_eq!(reader.read_mpint().unwrap(),
+               &[0x09, 0xa3, 0x78, 0xf9, 0xb2, 0xe3, 0x32, 0xa7]);
+    assert!(reader.is_empty());
+
+    // negative
+    assert!(Reader::new(&[0, 0, 0, 2, 0xed, 0xcc]).read_mpint().is_err());
+    // redundant leading zeros
+    assert_eq!(Reader::new(&[0, 0, 0, 2, 0, 0x7f]).read_mpint().unwrap(),
+               &[0x7f]);
+    assert_eq!(Reader::new(&[0, 0, 0, 1, 0]).read_mpint().unwrap(), &[]);
+    // strict reading requires minimal encoding
+    let mut reader = Reader::new(writer.as_bytes());
+    assert_eq!(reader.read_mpint_strict().unwrap(), &[]);
+    assert_eq!(reader.read_mpint_strict().unwrap(), &[0x80]);
+    assert_eq!(reader.read_mpint_strict().unwrap(),
+               &[0x09, 0xa3, 0x78, 0xf9, 0xb2, 0xe3, 0x32, 0xa7]);
+    assert!(Reader::new(&[0, 0, 0, 2, 0, 0x7f]).read_mpint_strict().is_err());
+    assert!(Reader::new(&[0, 0, 0, 1, 0]).read_mpint_strict().is_err());
+    assert!(Reader::new(&[0, 0, 0, 1, 0x80]).read_mpint_strict().is_err());
+}
+
+#[test]
+#[should_panic(expected = "invalid name in name-list")]
+fn name_list_with_comma() {
+    Writer::new().write_name_list(&["a,b"]);
+}
+
+#[test]
+#[should_panic(expected = "invalid name in name-list")]
+fn name_list_with_empty_name() {
+    Writer::new().write_name_list(&["a", ""]);
+}
+
+#[test]
+fn invalid() {
+    // truncated string
+    let mut reader = Reader::new(&[0, 0, 0, 5, b'a', b'b']);
+    assert!(reader.read_bytes().is_err());
+    assert_eq!(reader.position(), 0);
+    assert_eq!(reader.remaining().len(), 6);
+    // invalid utf-8
+    assert!(Reader::new(&[0, 0, 0, 1, 0xff]).read_string().is_err());
+    // empty name in the list
+    assert!(Reader::new(b"\0\0\0\x04a,,b").read_name_list().is_err());
+    assert!(Reader::new(b"\0\0\0\x02a,").read_name_list().is_err());
+}
+
+#[test]
+fn public_key_blob() {
+    for name in &["rsa1024", "rsa2048", "ed25519",
+                  "ecdsa256", "ecdsa384", "ecdsa521"]
+    {
+        let text = read_file(format!("test-keys/{}.pub", name));
+        let expected = base64::decode(
+            text.split_whitespace().nth(1).unwrap()).unwrap();
+        let key = parse_public_key(&text).unwrap();
+        assert_eq!(key.to_wire_blob(), expected);
+        assert_eq!(PublicKey::from_wire_blob(&expected).unwrap(), key);
+    }
+    assert!(PublicKey::from_wire_blob(b"").is_err());
+    assert!(PublicKey::from_wire_blob(b"\0\0\0\x07ssh-foo").is_err());
+}